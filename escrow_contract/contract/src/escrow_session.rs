@@ -0,0 +1,98 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+
+use casper_contract::{
+    contract_api::{account, runtime, system},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+
+use casper_types::{account::AccountHash, contracts::ContractHash, runtime_args, U512};
+
+// Session code for calling purse-taking entry points of the escrow contract.
+//
+// A stored contract cannot move funds out of an account's main purse, so this
+// creates a temporary purse, funds it with `funding` motes from the caller's
+// main purse, calls `entry_point` with it as the `purse` argument, and sweeps
+// whatever is left (e.g. refunds or withdrawals) back to the main purse.
+// The contract is taken from `contract_hash`, falling back to the caller's
+// `group_escrow_contract` named key, or `{name_prefix}_group_escrow_contract`
+// for deployments installed with a `name_prefix`.
+
+const CONTRACT_KEY: &str = "group_escrow_contract";
+
+const ARG_CONTRACT_HASH: &str = "contract_hash";
+const ARG_NAME_PREFIX: &str = "name_prefix";
+const ARG_ENTRY_POINT: &str = "entry_point";
+const ARG_FUNDING: &str = "funding";
+const ARG_ESCROW_ID: &str = "escrow_id";
+const ARG_AMOUNT: &str = "amount";
+const ARG_TARGET_AMOUNT: &str = "target_amount";
+const ARG_PARTICIPANT: &str = "participant";
+const ARG_PURSE: &str = "purse";
+const ARG_IDEMPOTENCY_KEY: &str = "idempotency_key";
+const ARG_BENEFICIARIES: &str = "beneficiaries";
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let entry_point: String = runtime::get_named_arg(ARG_ENTRY_POINT);
+    let funding: u64 = runtime::get_named_arg(ARG_FUNDING);
+
+    let contract_hash: ContractHash = match runtime::try_get_named_arg(ARG_CONTRACT_HASH) {
+        Some(contract_hash) => contract_hash,
+        None => {
+            let contract_key = match runtime::try_get_named_arg::<String>(ARG_NAME_PREFIX) {
+                Some(name_prefix) => format!("{}_{}", name_prefix, CONTRACT_KEY),
+                None => String::from(CONTRACT_KEY),
+            };
+            runtime::get_key(&contract_key)
+                .unwrap_or_revert()
+                .into_hash_addr()
+                .map(ContractHash::new)
+                .unwrap_or_revert()
+        }
+    };
+
+    let main_purse = account::get_main_purse();
+    let temp_purse = system::create_purse();
+    if funding > 0 {
+        system::transfer_from_purse_to_purse(main_purse, temp_purse, U512::from(funding), None)
+            .unwrap_or_revert();
+    }
+
+    let mut args = runtime_args! {
+        ARG_PURSE => temp_purse,
+    };
+    if let Some(escrow_id) = runtime::try_get_named_arg::<u64>(ARG_ESCROW_ID) {
+        args.insert(ARG_ESCROW_ID, escrow_id).unwrap_or_revert();
+    }
+    if let Some(amount) = runtime::try_get_named_arg::<u64>(ARG_AMOUNT) {
+        args.insert(ARG_AMOUNT, amount).unwrap_or_revert();
+    }
+    if let Some(target_amount) = runtime::try_get_named_arg::<u64>(ARG_TARGET_AMOUNT) {
+        args.insert(ARG_TARGET_AMOUNT, target_amount)
+            .unwrap_or_revert();
+    }
+    if let Some(participant) = runtime::try_get_named_arg::<AccountHash>(ARG_PARTICIPANT) {
+        args.insert(ARG_PARTICIPANT, participant).unwrap_or_revert();
+    }
+    if let Some(idempotency_key) = runtime::try_get_named_arg::<String>(ARG_IDEMPOTENCY_KEY) {
+        args.insert(ARG_IDEMPOTENCY_KEY, idempotency_key)
+            .unwrap_or_revert();
+    }
+    if let Some(beneficiaries) = runtime::try_get_named_arg::<Vec<AccountHash>>(ARG_BENEFICIARIES) {
+        args.insert(ARG_BENEFICIARIES, beneficiaries)
+            .unwrap_or_revert();
+    }
+
+    runtime::call_contract::<String>(contract_hash, &entry_point, args);
+
+    let leftover = system::get_purse_balance(temp_purse).unwrap_or_revert();
+    if !leftover.is_zero() {
+        system::transfer_from_purse_to_purse(temp_purse, main_purse, leftover, None)
+            .unwrap_or_revert();
+    }
+}
@@ -4,6 +4,7 @@
 extern crate alloc;
 
 use alloc::{
+    collections::BTreeMap,
     string::{String, ToString},
     vec,
     vec::Vec,
@@ -17,20 +18,136 @@ use casper_contract::{
 };
 
 use casper_types::{
-    CLType, CLValue, U512,
+    CLType, CLTyped, CLValue, U512,
+    bytesrepr::{Bytes, FromBytes, ToBytes},
     EntryPointAccess, EntryPointType, EntryPoints,
     Parameter,
-    contracts::{EntryPoint, NamedKeys},
+    contracts::{ContractHash, EntryPoint, NamedKeys},
     account::AccountHash,
+    runtime_args, Key,
 };
 
-/// ================= CONSTANTS =================
+// ================= CONSTANTS =================
 
 const CONTRACT_KEY: &str = "group_escrow_contract";
 const CONTRACT_PACKAGE_KEY: &str = "group_escrow_package";
 const CONTRACT_ACCESS_KEY: &str = "group_escrow_access";
 const CONTRACT_VERSION_KEY: &str = "contract_version";
 const CONTRACT_PURSE_KEY: &str = "contract_purse";
+const ADMIN_KEY: &str = "admin";
+const CONTRACT_EVENT_COUNTER_KEY: &str = "contract_event_counter";
+const TOTAL_REFUNDED_KEY: &str = "total_refunded";
+const TOTAL_SETTLED_KEY: &str = "total_settled";
+const SETTLEMENT_DELAY_KEY: &str = "settlement_delay";
+const MAX_ESCROWS_PER_CREATOR_KEY: &str = "max_escrows_per_creator";
+/// Install-time switch for event writes. Turning it off saves gas but leaves
+/// indexers that follow the `event_*` keys with nothing to read.
+const EVENTS_ENABLED_KEY: &str = "events_enabled";
+const LEAVE_FEE_BPS_KEY: &str = "leave_fee_bps";
+/// Leave fee, in basis points, for a refund taken at the escrow's deadline.
+/// When above `leave_fee_bps`, the fee rises linearly from `leave_fee_bps`
+/// at the participant's join time to this at the deadline.
+const LATE_LEAVE_FEE_BPS_KEY: &str = "late_leave_fee_bps";
+const FEE_RECEIVER_KEY: &str = "fee_receiver";
+/// Milliseconds after completion during which a creator can still refund
+/// everyone in escrows created with `grace_refund`.
+const POST_COMPLETE_GRACE_KEY: &str = "post_complete_grace";
+/// Refundable deposit, in motes, taken from the creator's `purse` for every
+/// new escrow and returned once the escrow is terminal.
+const CREATION_DEPOSIT_KEY: &str = "creation_deposit";
+/// Milliseconds after creation after which an escrow that is still not
+/// terminal counts as abandoned and its deposit can be forfeited.
+const DEPOSIT_TIMEOUT_KEY: &str = "deposit_timeout";
+/// Share of an escrow's fully-paid participants, in basis points, that must
+/// be exceeded by `vote_cancel` votes before a disputed escrow is cancelled.
+const CANCEL_VOTE_THRESHOLD_BPS_KEY: &str = "cancel_vote_threshold_bps";
+/// Milliseconds from creation used as the deadline when `create_escrow` gets
+/// no `deadline` argument. 0 leaves such escrows without a deadline.
+const DEFAULT_DEADLINE_OFFSET_KEY: &str = "default_deadline_offset";
+/// Minimum milliseconds between two `nudge` calls by the same participant
+/// on the same escrow.
+const NUDGE_INTERVAL_KEY: &str = "nudge_interval";
+/// Account that receives yield rounding dust when a withdraw-mode escrow
+/// completes. Unset means each escrow's creator.
+const DUST_RECIPIENT_KEY: &str = "dust_recipient";
+/// Compliance allowlist contract every joining account must be verified by.
+/// Unset means no verification.
+const VERIFIER_KEY: &str = "verifier";
+/// Oracle `get_value_in_quote` prices escrows with. Unset means no quotes.
+const PRICE_ORACLE_KEY: &str = "price_oracle";
+/// Whether `clone_escrow` is open to anyone rather than only the source
+/// escrow's creator.
+const ANYONE_CAN_CLONE_KEY: &str = "anyone_can_clone";
+/// Whether an escrow's creator, and not only the admin, may `purge_escrow` it.
+const CREATOR_CAN_PURGE_KEY: &str = "creator_can_purge";
+/// Block time (milliseconds) of the install. Only set through the named keys
+/// handed to `storage::new_contract`, so it is never rewritten afterwards.
+const INSTALLED_AT_KEY: &str = "installed_at";
+/// Most escrows one account may create within `CREATION_RATE_WINDOW_KEY`
+/// milliseconds. 0 disables the rate limit.
+const CREATION_RATE_LIMIT_KEY: &str = "creation_rate_limit";
+const CREATION_RATE_WINDOW_KEY: &str = "creation_rate_window";
+/// Milliseconds an open escrow may go without a new participant before
+/// `auto_cancel_idle` can cancel it. 0 disables idle cancellation.
+const IDLE_CANCEL_WINDOW_KEY: &str = "idle_cancel_window";
+/// Milliseconds a funded escrow must wait after its last new participant
+/// before it can settle, so late joiners have time to reconsider and leave.
+/// Restarts with every join. 0 disables the wait.
+const MIN_SETTLE_DELAY_AFTER_LAST_JOIN_KEY: &str = "min_settle_delay_after_last_join";
+/// Motes paid from the fee pool to whoever calls `auto_cancel_idle`, capped
+/// at what the pool holds.
+const IDLE_CANCEL_BOUNTY_KEY: &str = "idle_cancel_bounty";
+/// Contract purse funded through `fund_fee_pool` that keeper bounties are
+/// paid from. Created on first funding.
+const FEE_POOL_KEY: &str = "fee_pool";
+/// Human-readable entry point schema generated from `call()`'s `EntryPoints`.
+const ABI_KEY: &str = "abi";
+/// Append-only list of contract versions the package has had.
+const VERSIONS_KEY: &str = "versions";
+/// The contract's own hash, the same one installed under `CONTRACT_KEY` in
+/// the installer's account.
+const SELF_HASH_KEY: &str = "self_hash";
+/// Set by the admin's `freeze` to halt creation, joins, payments and
+/// settlement while leaving refunds, leaves, cancellations and withdrawals open.
+const FROZEN_KEY: &str = "frozen";
+
+const DEFAULT_MAX_ESCROWS_PER_CREATOR: u64 = 50;
+const MAX_SPLIT_SCHEDULE_SLOTS: u64 = 100;
+const MAX_MEMO_LENGTH: usize = 256;
+const MAX_ENCRYPTED_MEMO_LENGTH: usize = 1024;
+const MAX_METADATA_KEY_LENGTH: usize = 64;
+const MAX_METADATA_VALUE_LENGTH: usize = 256;
+const MAX_METADATA_ENTRIES: usize = 20;
+const MAX_TAGS_PER_ESCROW: usize = 5;
+const MAX_TAG_LENGTH: usize = 32;
+const MAX_DISPLAY_NAME_LENGTH: usize = 32;
+const MAX_ESCROW_BATCH_SIZE: u64 = 10;
+const BPS_DENOMINATOR: u64 = 10_000;
+const MAX_TEMPLATE_NAME_LENGTH: usize = 64;
+const MAX_RECIPIENTS: usize = 20;
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 64;
+const MAX_PAGE_SIZE: u64 = 50;
+const MIN_PARTICIPANTS: u64 = 2;
+const DEFAULT_CANCEL_VOTE_THRESHOLD_BPS: u64 = 5_000;
+const DEFAULT_NUDGE_INTERVAL: u64 = 60 * 60 * 1000;
+const DEFAULT_CREATION_RATE_WINDOW: u64 = 60 * 60 * 1000;
+const MAX_WAITLIST_LENGTH: usize = 50;
+/// Decimals of native CSPR: amounts are held in motes, 10^9 to the CSPR.
+const CSPR_DECIMALS: u8 = 9;
+/// Contract-level named keys every entry point may rely on; `ping` reverts
+/// if any is missing.
+const REQUIRED_KEYS: [&str; 6] = [
+    ADMIN_KEY,
+    TOTAL_REFUNDED_KEY,
+    TOTAL_SETTLED_KEY,
+    SETTLEMENT_DELAY_KEY,
+    FEE_RECEIVER_KEY,
+    INSTALLED_AT_KEY,
+];
+const PING_RESPONSE: u64 = 1;
+/// `remaining` reported by `get_timing` for escrows without a deadline.
+const NO_DEADLINE_REMAINING: u64 = u64::MAX;
+const DEFAULT_DEPOSIT_TIMEOUT: u64 = 30 * 24 * 60 * 60 * 1000;
 
 const EP_CREATE_ESCROW: &str = "create_escrow";
 const EP_JOIN_ESCROW: &str = "join_escrow";
@@ -46,14 +163,288 @@ const EP_DEPOSIT_YIELD: &str = "deposit_yield";
 const EP_GET_ESCROW_BALANCE: &str = "get_escrow_balance";
 const EP_GET_PARTICIPANT_YIELD: &str = "get_participant_yield";
 const EP_GET_CONTRACT_PURSE: &str = "get_contract_purse";
+const EP_GET_DEADLINE: &str = "get_deadline";
+const EP_PAY_INSTALLMENT: &str = "pay_installment";
+const EP_GET_PARTICIPANT_PAID: &str = "get_participant_paid";
+const EP_GET_ADMIN: &str = "get_admin";
+const EP_TRANSFER_ADMIN: &str = "transfer_admin";
+const EP_FINALIZE_SETTLEMENT: &str = "finalize_settlement";
+const EP_RAISE_DISPUTE: &str = "raise_dispute";
+const EP_GET_SETTLE_AFTER: &str = "get_settle_after";
+const EP_GET_CREATOR_EXPOSURE: &str = "get_creator_exposure";
+const EP_GET_SPLIT_SCHEDULE: &str = "get_split_schedule";
+const EP_GET_EVENTS_ENABLED: &str = "get_events_enabled";
+const EP_UPDATE_MEMO: &str = "update_memo";
+const EP_CREATE_ESCROW_BATCH: &str = "create_escrow_batch";
+const EP_APPROVE_SETTLEMENT: &str = "approve_settlement";
+const EP_VETO_SETTLEMENT: &str = "veto_settlement";
+const EP_GET_LEAVE_FEE: &str = "get_leave_fee";
+const EP_GET_PARTICIPANT_COUNT: &str = "get_participant_count";
+const EP_SAVE_TEMPLATE: &str = "save_template";
+const EP_CREATE_FROM_TEMPLATE: &str = "create_from_template";
+const EP_DELETE_TEMPLATE: &str = "delete_template";
+const EP_GET_TEMPLATE: &str = "get_template";
+const EP_RECONCILE: &str = "reconcile";
+const EP_GET_GATING_NFT: &str = "get_gating_nft";
+const EP_FINALIZE_COMPLETE: &str = "finalize_complete";
+const EP_REFUND_AFTER_COMPLETE: &str = "refund_after_complete";
+const EP_ESCROW_EXISTS: &str = "escrow_exists";
+const EP_CREATE_ESCROW_MULTI_RECIPIENT: &str = "create_escrow_multi_recipient";
+const EP_GET_RECIPIENTS: &str = "get_recipients";
+const EP_GET_CONFIG: &str = "get_config";
+const EP_GET_ESCROWS_BY_STATUS: &str = "get_escrows_by_status";
+const EP_GET_UNLOCK_TIME: &str = "get_unlock_time";
+const EP_GET_ABI: &str = "get_abi";
+const EP_GET_CREATION_DEPOSIT: &str = "get_creation_deposit";
+const EP_CLAIM_DEPOSIT: &str = "claim_deposit";
+const EP_FORFEIT_DEPOSIT: &str = "forfeit_deposit";
+const EP_SHRINK_ESCROW: &str = "shrink_escrow";
+const EP_VOTE_CANCEL: &str = "vote_cancel";
+const EP_GET_CANCEL_VOTES: &str = "get_cancel_votes";
+const EP_GET_INSTALL_TIME: &str = "get_install_time";
+const EP_GET_VERSIONS: &str = "get_versions";
+const EP_NUDGE: &str = "nudge";
+const EP_JOIN_WAITLIST: &str = "join_waitlist";
+const EP_GET_WAITLIST: &str = "get_waitlist";
+const EP_GET_DUST: &str = "get_dust";
+const EP_CHECK_CONDITION: &str = "check_condition";
+const EP_REFUND_UNMET_CONDITION: &str = "refund_unmet_condition";
+const EP_CLONE_ESCROW: &str = "clone_escrow";
+const EP_REFUND_BATCH: &str = "refund_batch";
+const EP_PING: &str = "ping";
+const EP_PAUSE_ESCROW: &str = "pause_escrow";
+const EP_UNPAUSE_ESCROW: &str = "unpause_escrow";
+const EP_GET_ESCROW_PAUSED: &str = "get_escrow_paused";
+const EP_GET_TIMING: &str = "get_timing";
+const EP_GET_ENCRYPTED_MEMO: &str = "get_encrypted_memo";
+const EP_PREVIEW_SETTLEMENT: &str = "preview_settlement";
+const EP_CREATE_RECURRING_ESCROW: &str = "create_recurring_escrow";
+const EP_GET_RECURRENCE_CHAIN: &str = "get_recurrence_chain";
+const EP_UPDATE_REFUND_ADDRESS: &str = "update_refund_address";
+const EP_GET_REFUND_ADDRESS: &str = "get_refund_address";
+const EP_GET_GAS_REBATE: &str = "get_gas_rebate";
+const EP_PURGE_ESCROW: &str = "purge_escrow";
+const EP_CAN_JOIN: &str = "can_join";
+const EP_CREATE_OPEN_POT: &str = "create_open_pot";
+const EP_CONTRIBUTE: &str = "contribute";
+const EP_GET_POT_BALANCE: &str = "get_pot_balance";
+const EP_GET_CONTRIBUTIONS: &str = "get_contributions";
+const EP_SET_METADATA: &str = "set_metadata";
+const EP_GET_METADATA: &str = "get_metadata";
+const EP_GET_SETTLEMENT_PURSE: &str = "get_settlement_purse";
+const EP_GET_CREATION_ALLOWANCE: &str = "get_creation_allowance";
+const EP_GET_SELF_HASH: &str = "get_self_hash";
+const EP_AUTO_CANCEL_IDLE: &str = "auto_cancel_idle";
+const EP_FUND_FEE_POOL: &str = "fund_fee_pool";
+const EP_GET_FEE_POOL: &str = "get_fee_pool";
+const EP_SETTLE_AT_DEADLINE: &str = "settle_at_deadline";
+const EP_TRANSFER_STAKE: &str = "transfer_stake";
+const EP_GET_DECIMALS: &str = "get_decimals";
+const EP_CHECK_MEMBERSHIPS: &str = "check_memberships";
+const EP_REVIVE_ESCROW: &str = "revive_escrow";
+const EP_PREVIEW_LEAVE_REFUND: &str = "preview_leave_refund";
+const EP_GET_ESCROW_ADMIN: &str = "get_escrow_admin";
+const EP_RESOLVE_DISPUTE: &str = "resolve_dispute";
+const EP_ADMIN_REFUND_PARTICIPANT: &str = "admin_refund_participant";
+const EP_GET_VERIFIER: &str = "get_verifier";
+const EP_GET_ESCROWS_BY_TAG: &str = "get_escrows_by_tag";
+const EP_GET_ACCUMULATED_CAP: &str = "get_accumulated_cap";
+const EP_GET_SLOT_AMOUNT: &str = "get_slot_amount";
+const EP_FREEZE: &str = "freeze";
+const EP_UNFREEZE: &str = "unfreeze";
+const EP_IS_FROZEN: &str = "is_frozen";
+const EP_GET_EARLIEST_SETTLE_TIME: &str = "get_earliest_settle_time";
+const EP_GET_ACCOUNT_POSITIONS: &str = "get_account_positions";
+const EP_SET_DISPLAY_NAME: &str = "set_display_name";
+const EP_GET_PARTICIPANT_NAMES: &str = "get_participant_names";
+const EP_SETTLE_BATCH: &str = "settle_batch";
+const EP_GET_VALUE_IN_QUOTE: &str = "get_value_in_quote";
+const EP_FUND_SLOTS: &str = "fund_slots";
+const EP_GET_CHARITY_CONFIG: &str = "get_charity_config";
+const EP_GET_PENDING_OPERATIONS: &str = "get_pending_operations";
+// CEP-78 entry point used to check NFT ownership for gated escrows.
+const EP_NFT_BALANCE_OF: &str = "balance_of";
+// Entry point an oracle contract exposes for conditional escrows:
+// `is_condition_met(condition_key: String) -> bool`.
+const EP_ORACLE_IS_CONDITION_MET: &str = "is_condition_met";
+// Entry point the install-time verifier contract must expose:
+// `is_verified(account: Key) -> bool`, with `account` passed as a
+// `Key::Account`. It is called on every join and installment, and should be
+// a cheap read that does not revert for unknown accounts.
+const EP_VERIFIER_IS_VERIFIED: &str = "is_verified";
+// Entry point the install-time price oracle must expose:
+// `get_cspr_price() -> Option<u64>`, the price of one whole CSPR in the
+// quote currency's smallest unit (e.g. micro-USD), or `None` when it has no
+// fresh price. Only read by `get_value_in_quote`; no funds depend on it.
+const EP_PRICE_ORACLE_GET_CSPR_PRICE: &str = "get_cspr_price";
 
 const ARG_ESCROW_ID: &str = "escrow_id";
 const ARG_AMOUNT: &str = "amount";
 const ARG_PARTICIPANT: &str = "participant";
 const ARG_TARGET_AMOUNT: &str = "target_amount";
 const ARG_PURSE: &str = "purse";
-
-/// ================= HELPERS =================
+const ARG_DEADLINE: &str = "deadline";
+const ARG_ADMIN: &str = "admin";
+const ARG_NEW_ADMIN: &str = "new_admin";
+const ARG_SETTLEMENT_DELAY: &str = "settlement_delay";
+const ARG_CREATOR: &str = "creator";
+const ARG_MAX_ESCROWS_PER_CREATOR: &str = "max_escrows_per_creator";
+const ARG_EXCLUDE_CREATOR: &str = "exclude_creator";
+const ARG_EVENTS_ENABLED: &str = "events_enabled";
+const ARG_MEMO: &str = "memo";
+const ARG_NEW_MEMO: &str = "new_memo";
+const ARG_ESCROWS: &str = "escrows";
+const ARG_COSIGNER: &str = "cosigner";
+const ARG_LEAVE_FEE_BPS: &str = "leave_fee_bps";
+const ARG_LATE_LEAVE_FEE_BPS: &str = "late_leave_fee_bps";
+const ARG_ESCROW_ADMIN: &str = "escrow_admin";
+const ARG_CANCEL: &str = "cancel";
+const ARG_FEE_RECEIVER: &str = "fee_receiver";
+const ARG_TEMPLATE_NAME: &str = "template_name";
+const ARG_NUM_PARTICIPANTS: &str = "num_participants";
+const ARG_DEADLINE_OFFSET: &str = "deadline_offset";
+const ARG_GATING_NFT: &str = "gating_nft";
+const ARG_NFT_TOKEN_OWNER: &str = "token_owner";
+const ARG_SETTLE_TO_CREATOR: &str = "settle_to_creator";
+const ARG_GRACE_REFUND: &str = "grace_refund";
+const ARG_POST_COMPLETE_GRACE: &str = "post_complete_grace";
+const ARG_RECIPIENTS: &str = "recipients";
+const ARG_IDEMPOTENCY_KEY: &str = "idempotency_key";
+const ARG_STATUS: &str = "status";
+const ARG_OFFSET: &str = "offset";
+const ARG_LIMIT: &str = "limit";
+const ARG_MIN_STAKE_DURATION: &str = "min_stake_duration";
+const ARG_CREATION_DEPOSIT: &str = "creation_deposit";
+const ARG_DEPOSIT_TIMEOUT: &str = "deposit_timeout";
+const ARG_CANCEL_VOTE_THRESHOLD_BPS: &str = "cancel_vote_threshold_bps";
+const ARG_DEFAULT_DEADLINE_OFFSET: &str = "default_deadline_offset";
+const ARG_NUDGE_INTERVAL: &str = "nudge_interval";
+const ARG_DUST_RECIPIENT: &str = "dust_recipient";
+const ARG_VERIFIER_CONTRACT: &str = "verifier_contract";
+const ARG_TAGS: &str = "tags";
+const ARG_TAG: &str = "tag";
+const ARG_MAX_ACCUMULATED: &str = "max_accumulated";
+const ARG_SLOT_INDEX: &str = "slot_index";
+const ARG_DISPLAY_NAME: &str = "display_name";
+const ARG_PRICE_ORACLE: &str = "price_oracle";
+const ARG_BENEFICIARIES: &str = "beneficiaries";
+const ARG_CHARITY: &str = "charity";
+const ARG_CHARITY_BPS: &str = "charity_bps";
+const ARG_ORACLE: &str = "oracle";
+const ARG_CONDITION_KEY: &str = "condition_key";
+const ARG_KEEP_EXCESS: &str = "keep_excess";
+const ARG_SOURCE_ESCROW_ID: &str = "source_escrow_id";
+const ARG_ANYONE_CAN_CLONE: &str = "anyone_can_clone";
+const ARG_CREATOR_CAN_PURGE: &str = "creator_can_purge";
+const ARG_CREATION_RATE_LIMIT: &str = "creation_rate_limit";
+const ARG_CREATION_RATE_WINDOW: &str = "creation_rate_window";
+const ARG_IDLE_CANCEL_WINDOW: &str = "idle_cancel_window";
+const ARG_MIN_SETTLE_DELAY_AFTER_LAST_JOIN: &str = "min_settle_delay_after_last_join";
+const ARG_IDLE_CANCEL_BOUNTY: &str = "idle_cancel_bounty";
+const ARG_SETTLE_PARTIAL_AT_DEADLINE: &str = "settle_partial_at_deadline";
+const ARG_ESCROW_IDS: &str = "escrow_ids";
+const ARG_MAX_COUNT: &str = "max_count";
+const ARG_ENCRYPTED_MEMO: &str = "encrypted_memo";
+const ARG_NAME_PREFIX: &str = "name_prefix";
+const ARG_NEW_REFUND: &str = "new_refund";
+const ARG_GAS_REBATE: &str = "gas_rebate";
+const ARG_ACCOUNT: &str = "account";
+const ARG_MIN_CONTRIBUTION: &str = "min_contribution";
+const ARG_KEY: &str = "key";
+const ARG_VALUE: &str = "value";
+const ARG_SETTLEMENT_PURSE: &str = "settlement_purse";
+
+const STATUS_OPEN: u8 = 0;
+const STATUS_READY_TO_SETTLE: u8 = 1;
+const STATUS_COMPLETED: u8 = 2;
+const STATUS_DISPUTED: u8 = 3;
+const STATUS_CANCELLED: u8 = 4;
+/// Partway through `refund_batch`; becomes CANCELLED with the last batch.
+const STATUS_REFUNDING: u8 = 5;
+
+/// Roles reported by `get_account_positions`.
+const ROLE_CREATOR: u8 = 0;
+const ROLE_PARTICIPANT: u8 = 1;
+
+const ERR_NOT_ADMIN: u16 = 107;
+const ERR_LEDGER_OVERFLOW: u16 = 108;
+const ERR_SETTLEMENT_LOCKED: u16 = 109;
+const ERR_NOT_READY_TO_SETTLE: u16 = 110;
+const ERR_NOT_PARTICIPANT: u16 = 111;
+const ERR_ESCROW_DISPUTED: u16 = 112;
+const ERR_TOO_MANY_ESCROWS: u16 = 113;
+const ERR_ZERO_AMOUNT: u16 = 114;
+const ERR_CREATOR_EXCLUDED: u16 = 115;
+const ERR_MEMO_TOO_LONG: u16 = 116;
+const ERR_NOT_CREATOR: u16 = 117;
+const ERR_BATCH_TOO_LARGE: u16 = 118;
+const ERR_SETTLEMENT_NOT_APPROVED: u16 = 119;
+const ERR_NOT_SIGNER: u16 = 120;
+const ERR_FEE_EXCEEDS_REFUND: u16 = 121;
+const ERR_INVALID_TEMPLATE: u16 = 122;
+const ERR_TEMPLATE_NOT_FOUND: u16 = 123;
+const ERR_NOT_NFT_HOLDER: u16 = 124;
+const ERR_PAID_OUT_ON_SETTLEMENT: u16 = 125;
+const ERR_PAYOUT_TRANSFER_FAILED: u16 = 126;
+const ERR_NO_PENDING_PAYOUT: u16 = 127;
+const ERR_GRACE_ACTIVE: u16 = 128;
+const ERR_GRACE_EXPIRED: u16 = 129;
+const ERR_INVALID_RECIPIENTS: u16 = 130;
+const ERR_INVALID_IDEMPOTENCY_KEY: u16 = 131;
+const ERR_ALREADY_FULLY_PAID: u16 = 132;
+const ERR_STAKE_LOCKED: u16 = 133;
+const ERR_NO_DEPOSIT: u16 = 134;
+const ERR_ESCROW_NOT_TERMINAL: u16 = 135;
+const ERR_DEPOSIT_NOT_FORFEITABLE: u16 = 136;
+const ERR_ESCROW_NOT_OPEN: u16 = 137;
+const ERR_INVALID_PARTICIPANT_COUNT: u16 = 138;
+const ERR_NOT_DISPUTED: u16 = 139;
+const ERR_ALREADY_VOTED: u16 = 140;
+const ERR_NUDGE_TOO_SOON: u16 = 141;
+const ERR_CONDITION_NOT_MET: u16 = 142;
+const ERR_NO_ORACLE: u16 = 143;
+const ERR_CONDITION_REFUND_LOCKED: u16 = 144;
+const ERR_REFUND_IN_PROGRESS: u16 = 145;
+const ERR_UNHEALTHY: u16 = 146;
+const ERR_ESCROW_PAUSED: u16 = 147;
+const ERR_ENCRYPTED_MEMO_TOO_LONG: u16 = 148;
+const ERR_ESCROW_NOT_FULL: u16 = 149;
+const ERR_ALREADY_WAITLISTED: u16 = 150;
+const ERR_WAITLIST_FULL: u16 = 151;
+const ERR_SPLIT_TOO_SMALL: u16 = 152;
+const ERR_INVALID_GAS_REBATE: u16 = 153;
+const ERR_ESCROW_NOT_PURGEABLE: u16 = 154;
+const ERR_NOT_OPEN_POT: u16 = 155;
+const ERR_CONTRIBUTION_TOO_SMALL: u16 = 156;
+const ERR_INVALID_METADATA: u16 = 157;
+const ERR_INVALID_SETTLEMENT_PURSE: u16 = 158;
+const ERR_RATE_LIMITED: u16 = 159;
+const ERR_ESCROW_NOT_IDLE: u16 = 160;
+const ERR_NOT_PARTIAL_SETTLEMENT: u16 = 161;
+const ERR_DEADLINE_NOT_REACHED: u16 = 162;
+const ERR_SPLIT_MISMATCH: u16 = 163;
+const ERR_NO_OPEN_SLOT: u16 = 164;
+const ERR_ESCROW_EXPIRED: u16 = 165;
+const ERR_ESCROW_NOT_EXPIRED: u16 = 166;
+const ERR_EXPIRY_REFUNDS_CLAIMED: u16 = 167;
+const ERR_INVALID_DEADLINE: u16 = 168;
+const ERR_NOT_ESCROW_ADMIN: u16 = 169;
+const ERR_NOT_VERIFIED: u16 = 170;
+const ERR_INVALID_TAGS: u16 = 171;
+const ERR_CAP_EXCEEDED: u16 = 172;
+const ERR_SLOT_OUT_OF_RANGE: u16 = 173;
+const ERR_SLOT_FILLED: u16 = 174;
+const ERR_FROZEN: u16 = 175;
+const ERR_INVALID_DISPLAY_NAME: u16 = 176;
+const ERR_ORACLE_UNAVAILABLE: u16 = 177;
+const ERR_ALREADY_JOINED: u16 = 178;
+const ERR_INSOLVENT: u16 = 179;
+const ERR_INVALID_CHARITY: u16 = 180;
+const ERR_ESCROW_EXISTS: u16 = 181;
+const ERR_INVALID_BPS: u16 = 182;
+const ERR_UNEVEN_TEMPLATE_SPLIT: u16 = 183;
+
+// ================= HELPERS =================
 
 fn escrow_key(id: u64) -> String {
     format!("escrow_{}", id)
@@ -107,341 +498,491 @@ fn escrow_yield_purse_key(id: u64) -> String {
     format!("escrow_{}_yield_purse", id)
 }
 
-fn escrow_event_counter_key(id: u64) -> String {
-    format!("escrow_{}_event_counter", id)
+/// Deadlines are stored in milliseconds since the Unix epoch, the same unit
+/// `runtime::get_blocktime()` returns. 0 means the escrow has no deadline.
+fn escrow_deadline_key(id: u64) -> String {
+    format!("escrow_{}_deadline", id)
 }
 
-fn emit_event(event_name: &str, escrow_id: u64, data: &str) {
-    let counter_key = escrow_event_counter_key(escrow_id);
-    let counter = match runtime::get_key(&counter_key) {
-        Some(key) => {
-            let uref = key.into_uref().unwrap_or_revert();
-            let current: u64 = storage::read(uref)
-                .unwrap_or_revert()
-                .unwrap_or_revert();
-            storage::write(uref, current + 1);
-            current + 1
-        }
-        None => {
-            let uref = storage::new_uref(1u64);
-            runtime::put_key(&counter_key, uref.into());
-            1u64
-        }
-    };
+fn escrow_paid_key(id: u64, participant: AccountHash) -> String {
+    format!("escrow_{}_paid_{}", id, participant)
+}
 
-    let event_key = format!("event_{}_{}", event_name, counter);
-    let event_uref = storage::new_uref(data.to_string());
-    runtime::put_key(&event_key, event_uref.into());
+/// Everyone who has paid an installment, including those still below the
+/// split who are not yet in the participant list.
+fn escrow_payers_key(id: u64) -> String {
+    format!("escrow_{}_payers", id)
 }
 
-fn get_main_purse() -> casper_types::URef {
-    let key = runtime::get_key(CONTRACT_PURSE_KEY).unwrap_or_revert();
-    key.into_uref().unwrap_or_revert()
+fn escrow_joined_count_key(id: u64) -> String {
+    format!("escrow_{}_joined_count", id)
 }
 
-/// ================= ENTRY POINTS =================
+fn escrow_status_key(id: u64) -> String {
+    format!("escrow_{}_status", id)
+}
 
-#[no_mangle]
-pub extern "C" fn create_escrow() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
-    let target_amount: u64 = runtime::get_named_arg(ARG_TARGET_AMOUNT);
+/// Block time (milliseconds) from which a ready escrow may be settled.
+fn escrow_settle_after_key(id: u64) -> String {
+    format!("escrow_{}_settle_after", id)
+}
 
-    let key = escrow_key(escrow_id);
-    let amount_uref = storage::new_uref(amount);
-    runtime::put_key(&key, amount_uref.into());
+fn escrow_creator_key(id: u64) -> String {
+    format!("escrow_{}_creator", id)
+}
 
-    let target_key = escrow_target_key(escrow_id);
-    let target_uref = storage::new_uref(target_amount);
-    runtime::put_key(&target_key, target_uref.into());
+fn escrow_exclude_creator_key(id: u64) -> String {
+    format!("escrow_{}_exclude_creator", id)
+}
 
-    let joined_key = escrow_joined_key(escrow_id);
-    let joined_uref = storage::new_uref(false);
-    runtime::put_key(&joined_key, joined_uref.into());
+/// Operational admin of a managed escrow, set at creation. They may pause,
+/// unpause and resolve disputes, but never receive funds. Absent otherwise.
+fn escrow_admin_key(id: u64) -> String {
+    format!("escrow_{}_admin", id)
+}
 
-    let participants_key = escrow_participants_key(escrow_id);
-    let participants: Vec<AccountHash> = vec![];
-    let participants_uref = storage::new_uref(participants);
-    runtime::put_key(&participants_key, participants_uref.into());
+/// Only present for escrows created with a cosigner. Such escrows settle once
+/// both the creator and the cosigner have approved.
+fn escrow_cosigner_key(id: u64) -> String {
+    format!("escrow_{}_cosigner", id)
+}
 
-    let total_staked_key = escrow_total_staked_key(escrow_id);
-    let total_staked_uref = storage::new_uref(0u64);
-    runtime::put_key(&total_staked_key, total_staked_uref.into());
+fn escrow_creator_approved_key(id: u64) -> String {
+    format!("escrow_{}_creator_approved", id)
+}
 
-    let total_yield_key = escrow_total_yield_key(escrow_id);
-    let total_yield_uref = storage::new_uref(0u64);
-    runtime::put_key(&total_yield_key, total_yield_uref.into());
+fn escrow_cosigner_approved_key(id: u64) -> String {
+    format!("escrow_{}_cosigner_approved", id)
+}
 
-    let completed_key = escrow_completed_key(escrow_id);
-    let completed_uref = storage::new_uref(false);
-    runtime::put_key(&completed_key, completed_uref.into());
+/// When true, settlement pays the pooled principal and yield to the creator
+/// instead of leaving it for participants to withdraw.
+fn escrow_settle_to_creator_key(id: u64) -> String {
+    format!("escrow_{}_settle_to_creator", id)
+}
 
-    let escrow_purse = system::create_purse();
-    let escrow_purse_key = escrow_purse_key(escrow_id);
-    runtime::put_key(&escrow_purse_key, escrow_purse.into());
+/// When true, `settle_at_deadline` pays whatever was pooled to the creator
+/// once the deadline passes, even if the target was not reached.
+fn escrow_settle_partial_key(id: u64) -> String {
+    format!("escrow_{}_settle_partial", id)
+}
 
-    let yield_purse = system::create_purse();
-    let yield_purse_key = escrow_yield_purse_key(escrow_id);
-    runtime::put_key(&yield_purse_key, yield_purse.into());
+/// Milliseconds after completion before participants may withdraw.
+fn escrow_min_stake_duration_key(id: u64) -> String {
+    format!("escrow_{}_min_stake_duration", id)
+}
 
-    emit_event("escrow_created", escrow_id, &format!("amount:{},target:{}", amount, target_amount));
+/// Block time (milliseconds) at which the escrow completed.
+fn escrow_completed_at_key(id: u64) -> String {
+    format!("escrow_{}_completed_at", id)
+}
 
-    runtime::ret(
-        CLValue::from_t(format!("Escrow {} created with target {}", escrow_id, target_amount))
-            .unwrap_or_revert(),
-    );
+/// Block time (milliseconds) at which the escrow was created.
+fn escrow_created_at_key(id: u64) -> String {
+    format!("escrow_{}_created_at", id)
 }
 
-#[no_mangle]
-pub extern "C" fn join_escrow() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+/// Set once anyone takes a refund from the escrow after its deadline, which
+/// rules out reviving it.
+fn escrow_expiry_refunded_key(id: u64) -> String {
+    format!("escrow_{}_expiry_refunded", id)
+}
 
-    let joined_key = escrow_joined_key(escrow_id);
+/// Block time (milliseconds) of the participant's first payment since they
+/// last left, used to scale their leave fee.
+fn escrow_join_time_key(id: u64, participant: AccountHash) -> String {
+    format!("escrow_{}_join_time_{}", id, participant)
+}
 
-    match runtime::get_key(&joined_key) {
-        Some(key) => {
-            let uref = key.into_uref().unwrap_or_revert();
-            storage::write(uref, true);
-        }
-        None => {
-            let uref = storage::new_uref(true);
-            runtime::put_key(&joined_key, uref.into());
-        }
-    }
+/// Block time (milliseconds) at which the last new participant joined.
+/// Unset until the first join, in which case the creation time counts.
+fn escrow_last_join_time_key(id: u64) -> String {
+    format!("escrow_{}_last_join_time", id)
+}
 
-    let caller = runtime::get_caller();
+/// Creation deposit still held for the escrow, 0 once claimed or forfeited.
+fn escrow_deposit_key(id: u64) -> String {
+    format!("escrow_{}_deposit", id)
+}
 
-    let participants_key = escrow_participants_key(escrow_id);
-    match runtime::get_key(&participants_key) {
-        Some(key) => {
-            let participants_uref = key.into_uref().unwrap_or_revert();
-            let mut participants: Vec<AccountHash> = storage::read(participants_uref)
-                .unwrap_or_revert()
-                .unwrap_or_revert();
+fn escrow_deposit_purse_key(id: u64) -> String {
+    format!("escrow_{}_deposit_purse", id)
+}
 
-            if !participants.contains(&caller) {
-                participants.push(caller);
-                storage::write(participants_uref, participants);
+/// Number of `vote_cancel` votes cast on the escrow.
+fn escrow_cancel_votes_key(id: u64) -> String {
+    format!("escrow_{}_cancel_votes", id)
+}
 
-                let liquid_balance_key = escrow_liquid_balance_key(escrow_id, caller);
-                let liquid_balance_uref = storage::new_uref(0u64);
-                runtime::put_key(&liquid_balance_key, liquid_balance_uref.into());
+fn escrow_cancel_voted_key(id: u64, voter: AccountHash) -> String {
+    format!("escrow_{}_cancel_voted_{}", id, voter)
+}
 
-                emit_event("liquid_staking_enabled", escrow_id, &format!("participant:{}", caller));
-            }
-        }
-        None => {}
-    }
+/// Block time (milliseconds) of the participant's last `nudge`.
+fn escrow_last_nudge_key(id: u64, participant: AccountHash) -> String {
+    format!("escrow_{}_last_nudge_{}", id, participant)
+}
 
-    emit_event("escrow_joined", escrow_id, &format!("participant:{}", caller));
+/// Yield left over by rounding each participant's share down, sent to the
+/// dust recipient at completion.
+fn escrow_dust_key(id: u64) -> String {
+    format!("escrow_{}_dust", id)
 }
 
-#[no_mangle]
-pub extern "C" fn stake() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
-    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+/// Oracle contract whose `is_condition_met` must confirm the escrow's
+/// condition before it can settle.
+fn escrow_oracle_key(id: u64) -> String {
+    format!("escrow_{}_oracle", id)
+}
 
-    let completed_key = escrow_completed_key(escrow_id);
-    let completed_storage_key = runtime::get_key(&completed_key).unwrap_or_revert();
-    let completed_uref = completed_storage_key.into_uref().unwrap_or_revert();
-    let is_completed: bool = storage::read(completed_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+/// Condition identifier passed to the oracle.
+fn escrow_condition_key(id: u64) -> String {
+    format!("escrow_{}_condition_key", id)
+}
 
-    if is_completed {
-        runtime::revert(casper_types::ApiError::User(103));
-    }
+/// Set once `check_condition` has seen the oracle report the condition met.
+fn escrow_condition_met_key(id: u64) -> String {
+    format!("escrow_{}_condition_met", id)
+}
 
-    let source_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
-    
-    let escrow_purse_key = escrow_purse_key(escrow_id);
-    let escrow_purse_storage_key = runtime::get_key(&escrow_purse_key).unwrap_or_revert();
-    let escrow_purse = escrow_purse_storage_key.into_uref().unwrap_or_revert();
+/// Whether installments above the split are kept in the pool as yield
+/// instead of being left in the payer's purse.
+fn escrow_keep_excess_key(id: u64) -> String {
+    format!("escrow_{}_keep_excess", id)
+}
 
-    system::transfer_from_purse_to_purse(
-        source_purse,
-        escrow_purse,
-        U512::from(amount),
-        None
-    ).unwrap_or_revert();
+/// Number of principal holders already paid back by `refund_batch`.
+fn escrow_refund_cursor_key(id: u64) -> String {
+    format!("escrow_{}_refund_cursor", id)
+}
 
-    let participants_key = escrow_participants_key(escrow_id);
-    let participants_storage_key = runtime::get_key(&participants_key).unwrap_or_revert();
-    let participants_uref = participants_storage_key.into_uref().unwrap_or_revert();
-    let mut participants: Vec<AccountHash> = storage::read(participants_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+/// Set by the creator to stop new joins and installments on this escrow.
+fn escrow_paused_key(id: u64) -> String {
+    format!("escrow_{}_paused", id)
+}
 
-    if !participants.contains(&participant) {
-        participants.push(participant);
-        storage::write(participants_uref, participants);
-    }
+/// Client-encrypted memo bytes. The contract stores them as given and never
+/// decrypts or inspects them.
+fn escrow_encrypted_memo_key(id: u64) -> String {
+    format!("escrow_{}_encrypted_memo", id)
+}
 
-    let stake_key = escrow_stake_key(escrow_id, participant);
-    match runtime::get_key(&stake_key) {
-        Some(key) => {
-            let uref = key.into_uref().unwrap_or_revert();
-            let current_stake: u64 = storage::read(uref)
-                .unwrap_or_revert()
-                .unwrap_or_revert();
-            storage::write(uref, current_stake + amount);
-        }
-        None => {
-            let uref = storage::new_uref(amount);
-            runtime::put_key(&stake_key, uref.into());
-        }
-    }
+/// Accounts queued for a slot on a full escrow, in the order they asked.
+fn escrow_waitlist_key(id: u64) -> String {
+    format!("escrow_{}_waitlist", id)
+}
 
-    let liquid_balance_key = escrow_liquid_balance_key(escrow_id, participant);
-    match runtime::get_key(&liquid_balance_key) {
-        Some(key) => {
-            let uref = key.into_uref().unwrap_or_revert();
-            let current_liquid: u64 = storage::read(uref)
-                .unwrap_or_revert()
-                .unwrap_or_revert();
-            storage::write(uref, current_liquid + amount);
-        }
-        None => {
-            let uref = storage::new_uref(amount);
-            runtime::put_key(&liquid_balance_key, uref.into());
-        }
-    }
+/// Whether the escrow reopens under a new id for the next period when it completes.
+fn escrow_recurring_key(id: u64) -> String {
+    format!("escrow_{}_recurring", id)
+}
 
-    let total_staked_key = escrow_total_staked_key(escrow_id);
-    let total_staked_storage_key = runtime::get_key(&total_staked_key).unwrap_or_revert();
-    let total_staked_uref = total_staked_storage_key.into_uref().unwrap_or_revert();
-    let current_total: u64 = storage::read(total_staked_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
-    storage::write(total_staked_uref, current_total + amount);
+/// Id of the escrow a recurring escrow reopened as.
+fn escrow_next_key(id: u64) -> String {
+    format!("escrow_{}_next", id)
+}
 
-    emit_event("staked", escrow_id, &format!("participant:{},amount:{},liquid_issued:{}", participant, amount, amount));
+/// Id of the recurring escrow this one was reopened from.
+fn escrow_prev_key(id: u64) -> String {
+    format!("escrow_{}_prev", id)
+}
 
-    runtime::ret(
-        CLValue::from_t(format!("Staked {} to escrow {} with liquid tokens", amount, escrow_id))
-            .unwrap_or_revert(),
-    );
+/// Account the participant's refunds are paid to instead of the purse or
+/// account they joined with.
+fn escrow_refund_address_key(id: u64, participant: AccountHash) -> String {
+    format!("escrow_{}_refund_address_{}", id, participant)
 }
 
-#[no_mangle]
-pub extern "C" fn refund() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+/// Fixed amount each staked participant gets back from the creator's
+/// proceeds on settlement to offset gas. Gas used is not visible on-chain,
+/// so this is configured at creation rather than measured.
+fn escrow_gas_rebate_key(id: u64) -> String {
+    format!("escrow_{}_gas_rebate", id)
+}
 
-    let completed_key = escrow_completed_key(escrow_id);
-    let completed_storage_key = runtime::get_key(&completed_key).unwrap_or_revert();
-    let completed_uref = completed_storage_key.into_uref().unwrap_or_revert();
-    let is_completed: bool = storage::read(completed_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+/// Total gas rebates paid out when the escrow settled.
+fn escrow_gas_rebates_paid_key(id: u64) -> String {
+    format!("escrow_{}_gas_rebates_paid", id)
+}
 
-    if is_completed {
-        runtime::revert(casper_types::ApiError::User(100));
-    }
+/// Purse the creator's proceeds are paid into instead of their account's
+/// main purse.
+fn escrow_settlement_purse_key(id: u64) -> String {
+    format!("escrow_{}_settlement_purse", id)
+}
 
-    let stake_key = escrow_stake_key(escrow_id, participant);
-    let stake_storage_key = runtime::get_key(&stake_key).unwrap_or_revert();
-    let stake_uref = stake_storage_key.into_uref().unwrap_or_revert();
-    let staked_amount: u64 = storage::read(stake_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+/// Set for open pots, which take `contribute` calls of any amount from the
+/// minimum up instead of fixed splits. The escrow's split holds the minimum.
+fn escrow_open_pot_key(id: u64) -> String {
+    format!("escrow_{}_open_pot", id)
+}
 
-    if staked_amount == 0 {
-        runtime::revert(casper_types::ApiError::User(104));
-    }
+/// One creator-set metadata value.
+fn escrow_metadata_key(id: u64, key: &str) -> String {
+    format!("escrow_{}_meta_{}", id, key)
+}
 
-    let escrow_purse_key = escrow_purse_key(escrow_id);
-    let escrow_purse_storage_key = runtime::get_key(&escrow_purse_key).unwrap_or_revert();
-    let escrow_purse = escrow_purse_storage_key.into_uref().unwrap_or_revert();
+/// Metadata keys set on the escrow, so they can be found again by `purge_escrow`.
+fn escrow_metadata_keys_key(id: u64) -> String {
+    format!("escrow_{}_metadata_keys", id)
+}
 
-    let target_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+/// Payees of a multi-recipient escrow as `(account, amount)` pairs summing to
+/// the target. Settlement splits the pool between them pro rata.
+fn escrow_recipients_key(id: u64) -> String {
+    format!("escrow_{}_recipients", id)
+}
 
-    system::transfer_from_purse_to_purse(
-        escrow_purse,
-        target_purse,
-        U512::from(staked_amount),
-        None
-    ).unwrap_or_revert();
+/// For settle-to-creator escrows: defer the creator payout by the contract's
+/// post-completion grace period so the creator can still refund everyone.
+fn escrow_grace_refund_key(id: u64) -> String {
+    format!("escrow_{}_grace_refund", id)
+}
 
-    storage::write(stake_uref, 0u64);
+/// True between completion and either `finalize_complete` or `refund_after_complete`.
+fn escrow_payout_pending_key(id: u64) -> String {
+    format!("escrow_{}_payout_pending", id)
+}
 
-    let liquid_balance_key = escrow_liquid_balance_key(escrow_id, participant);
-    match runtime::get_key(&liquid_balance_key) {
-        Some(key) => {
-            let uref = key.into_uref().unwrap_or_revert();
-            let current_liquid: u64 = storage::read(uref)
-                .unwrap_or_revert()
-                .unwrap_or_revert();
-            if current_liquid >= staked_amount {
-                storage::write(uref, current_liquid - staked_amount);
-            } else {
-                storage::write(uref, 0u64);
-            }
-        }
-        None => {}
-    }
+/// Block time (milliseconds) until which a deferred payout can be refunded.
+fn escrow_refundable_until_key(id: u64) -> String {
+    format!("escrow_{}_refundable_until", id)
+}
 
-    let total_staked_key = escrow_total_staked_key(escrow_id);
-    let total_staked_storage_key = runtime::get_key(&total_staked_key).unwrap_or_revert();
-    let total_staked_uref = total_staked_storage_key.into_uref().unwrap_or_revert();
-    let current_total: u64 = storage::read(total_staked_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
-    storage::write(total_staked_uref, current_total - staked_amount);
+/// Discovery tags of the escrow, each also indexed under `tag_escrows_key`.
+fn escrow_tags_key(id: u64) -> String {
+    format!("escrow_{}_tags", id)
+}
 
-    emit_event("refunded", escrow_id, &format!("participant:{},amount:{}", participant, staked_amount));
+/// `(charity, charity_bps)`: the share of settlement proceeds donated to
+/// `charity` before the creator or recipients are paid.
+fn escrow_charity_key(id: u64) -> String {
+    format!("escrow_{}_charity", id)
+}
 
-    runtime::ret(
-        CLValue::from_t(format!("Refunded {} from escrow {}", staked_amount, escrow_id))
-            .unwrap_or_revert(),
-    );
+/// Most principal the escrow may ever hold at once. Unset means no cap.
+fn escrow_max_accumulated_key(id: u64) -> String {
+    format!("escrow_{}_max_accumulated", id)
 }
 
-#[no_mangle]
-pub extern "C" fn deposit_yield() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
+/// CEP-78 contract whose holders may join; absent for ungated escrows.
+fn escrow_gating_nft_key(id: u64) -> String {
+    format!("escrow_{}_gating_nft", id)
+}
 
-    let source_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+/// Free-form description set by the creator, at most `MAX_MEMO_LENGTH` bytes.
+fn escrow_memo_key(id: u64) -> String {
+    format!("escrow_{}_memo", id)
+}
 
-    let yield_purse_key = escrow_yield_purse_key(escrow_id);
-    let yield_purse_storage_key = runtime::get_key(&yield_purse_key).unwrap_or_revert();
-    let yield_purse = yield_purse_storage_key.into_uref().unwrap_or_revert();
+/// Marker for an idempotency key a participant has already used on an escrow.
+fn escrow_idempotency_key(id: u64, participant: AccountHash, idempotency_key: &str) -> String {
+    format!("escrow_{}_idem_{}_{}", id, participant, idempotency_key)
+}
 
-    system::transfer_from_purse_to_purse(
-        source_purse,
-        yield_purse,
-        U512::from(amount),
-        None
-    ).unwrap_or_revert();
+/// Ids of all escrows currently in `status`, in no particular order.
+fn status_escrows_key(status: u8) -> String {
+    format!("status_{}_escrows", status)
+}
 
-    let total_yield_key = escrow_total_yield_key(escrow_id);
-    let total_yield_storage_key = runtime::get_key(&total_yield_key).unwrap_or_revert();
-    let total_yield_uref = total_yield_storage_key.into_uref().unwrap_or_revert();
-    let current_yield: u64 = storage::read(total_yield_uref)
+/// Ids of all escrows tagged with `tag`, in creation order.
+fn tag_escrows_key(tag: &str) -> String {
+    format!("tag_{}_escrows", tag)
+}
+
+/// Ids of the creator's escrows that have not completed yet.
+fn creator_escrows_key(creator: AccountHash) -> String {
+    format!("creator_{}_escrows", creator)
+}
+
+/// Display name the account set for itself, shared across all escrows.
+fn display_name_key(account: AccountHash) -> String {
+    format!("name_{}", account)
+}
+
+/// Ids of escrows the account has joined, in join order.
+fn account_escrows_key(account: AccountHash) -> String {
+    format!("account_{}_escrows", account)
+}
+
+/// The creator's current rate limit window as `(window_start, created)`.
+fn creator_creation_window_key(creator: AccountHash) -> String {
+    format!("creator_{}_creation_window", creator)
+}
+
+/// Saved escrow configuration as `(num_participants, deadline_offset, exclude_creator)`.
+/// The deadline offset is in milliseconds from creation, 0 meaning no deadline.
+fn template_key(creator: AccountHash, template_name: &str) -> String {
+    format!("template_{}_{}", creator, template_name)
+}
+
+fn escrow_event_counter_key(id: u64) -> String {
+    format!("escrow_{}_event_counter", id)
+}
+
+fn events_enabled() -> bool {
+    read_named_value_or(EVENTS_ENABLED_KEY, true)
+}
+
+fn emit_event(event_name: &str, escrow_id: u64, data: &str) {
+    if !events_enabled() {
+        return;
+    }
+
+    let counter_key = escrow_event_counter_key(escrow_id);
+    let counter = match runtime::get_key(&counter_key) {
+        Some(key) => {
+            let uref = key.into_uref().unwrap_or_revert();
+            let current: u64 = storage::read(uref)
+                .unwrap_or_revert()
+                .unwrap_or_revert();
+            storage::write(uref, current + 1);
+            current + 1
+        }
+        None => {
+            let uref = storage::new_uref(1u64);
+            runtime::put_key(&counter_key, uref.into());
+            1u64
+        }
+    };
+
+    let event_key = format!("event_{}_{}", event_name, counter);
+    let event_uref = storage::new_uref(data.to_string());
+    runtime::put_key(&event_key, event_uref.into());
+}
+
+/// Current block time in milliseconds, the unit used for every stored timestamp.
+fn now_millis() -> u64 {
+    u64::from(runtime::get_blocktime())
+}
+
+fn read_named_value<T: CLTyped + FromBytes>(name: &str) -> T {
+    let key = runtime::get_key(name).unwrap_or_revert();
+    let uref = key.into_uref().unwrap_or_revert();
+    storage::read(uref)
         .unwrap_or_revert()
-        .unwrap_or_revert();
-    storage::write(total_yield_uref, current_yield + amount);
+        .unwrap_or_revert()
+}
 
-    emit_event("yield_deposited", escrow_id, &format!("amount:{}", amount));
+fn read_named_value_or<T: CLTyped + FromBytes>(name: &str, default: T) -> T {
+    match runtime::get_key(name) {
+        Some(key) => {
+            let uref = key.into_uref().unwrap_or_revert();
+            storage::read(uref)
+                .unwrap_or_revert()
+                .unwrap_or_revert()
+        }
+        None => default,
+    }
+}
 
-    runtime::ret(
-        CLValue::from_t(format!("Deposited {} yield to escrow {}", amount, escrow_id))
-            .unwrap_or_revert(),
-    );
+fn write_named_value<T: CLTyped + ToBytes>(name: &str, value: T) {
+    match runtime::get_key(name) {
+        Some(key) => {
+            let uref = key.into_uref().unwrap_or_revert();
+            storage::write(uref, value);
+        }
+        None => {
+            let uref = storage::new_uref(value);
+            runtime::put_key(name, uref.into());
+        }
+    }
 }
 
-#[no_mangle]
-pub extern "C" fn complete_escrow() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+/// Moves an escrow to `status`, keeping the per-status indexes in step.
+fn set_status(escrow_id: u64, status: u8) {
+    let status_key = escrow_status_key(escrow_id);
+    if runtime::has_key(&status_key) {
+        let previous: u8 = read_named_value(&status_key);
+        let previous_key = status_escrows_key(previous);
+        let mut previous_escrows: Vec<u64> = read_named_value_or(&previous_key, Vec::new());
+        if let Some(index) = previous_escrows.iter().position(|id| *id == escrow_id) {
+            previous_escrows.swap_remove(index);
+            write_named_value(&previous_key, previous_escrows);
+        }
+    }
+
+    let index_key = status_escrows_key(status);
+    let mut escrows: Vec<u64> = read_named_value_or(&index_key, Vec::new());
+    escrows.push(escrow_id);
+    write_named_value(&index_key, escrows);
+
+    write_named_value(&status_key, status);
+}
+
+/// Credits `amount` of principal to a participant: adds them to the
+/// participant list and bumps their stake, liquid balance and the escrow total.
+fn credit_stake(escrow_id: u64, participant: AccountHash, amount: u64) {
+    let participants_key = escrow_participants_key(escrow_id);
+    let mut participants: Vec<AccountHash> = read_named_value(&participants_key);
+    if !participants.contains(&participant) {
+        participants.push(participant);
+        write_named_value(&participants_key, participants);
+        write_named_value(&escrow_last_join_time_key(escrow_id), now_millis());
+        add_to_account_escrows(participant, escrow_id);
+    }
+    record_join_time(escrow_id, participant);
+
+    let stake_key = escrow_stake_key(escrow_id, participant);
+    let current_stake: u64 = read_named_value_or(&stake_key, 0u64);
+    write_named_value(&stake_key, current_stake + amount);
+
+    // Every funding path counts the participant once their stake first
+    // covers the split.
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    if current_stake < split && current_stake + amount >= split {
+        let joined_count_key = escrow_joined_count_key(escrow_id);
+        let joined_count: u64 = read_named_value_or(&joined_count_key, 0u64);
+        write_named_value(&joined_count_key, joined_count + 1);
+    }
+
+    let liquid_balance_key = escrow_liquid_balance_key(escrow_id, participant);
+    let current_liquid: u64 = read_named_value_or(&liquid_balance_key, 0u64);
+    write_named_value(&liquid_balance_key, current_liquid + amount);
 
     let total_staked_key = escrow_total_staked_key(escrow_id);
-    let total_staked_storage_key = runtime::get_key(&total_staked_key).unwrap_or_revert();
-    let total_staked_uref = total_staked_storage_key.into_uref().unwrap_or_revert();
-    let total_staked: u64 = storage::read(total_staked_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+    let current_total: u64 = read_named_value(&total_staked_key);
+    if current_total.saturating_add(amount) > accumulated_cap(escrow_id) {
+        runtime::revert(casper_types::ApiError::User(ERR_CAP_EXCEEDED));
+    }
+    write_named_value(&total_staked_key, current_total + amount);
+
+    mark_ready_if_funded(escrow_id, current_total + amount);
+}
+
+/// The escrow's `max_accumulated`, or `u64::MAX` when it has none.
+fn accumulated_cap(escrow_id: u64) -> u64 {
+    read_named_value_or(&escrow_max_accumulated_key(escrow_id), u64::MAX)
+}
+
+/// Stores the optional `max_accumulated` argument of a create call.
+fn store_accumulated_cap(escrow_id: u64) {
+    if let Some(max_accumulated) = runtime::try_get_named_arg::<u64>(ARG_MAX_ACCUMULATED) {
+        let max_accumulated_key = escrow_max_accumulated_key(escrow_id);
+        let max_accumulated_uref = storage::new_uref(max_accumulated);
+        runtime::put_key(&max_accumulated_key, max_accumulated_uref.into());
+    }
+}
+
+/// Moves an open escrow to READY_TO_SETTLE once `total_staked` covers its
+/// target, starting the settlement timelock.
+fn mark_ready_if_funded(escrow_id: u64, total_staked: u64) {
+    let target_amount: u64 = read_named_value(&escrow_target_key(escrow_id));
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status == STATUS_OPEN && total_staked >= target_amount {
+        let settlement_delay: u64 = read_named_value_or(SETTLEMENT_DELAY_KEY, 0u64);
+        let settle_after = now_millis().saturating_add(settlement_delay);
+        set_status(escrow_id, STATUS_READY_TO_SETTLE);
+        write_named_value(&escrow_settle_after_key(escrow_id), settle_after);
+
+        emit_event("ready_to_settle", escrow_id, &format!("settle_after:{}", settle_after));
+    }
+}
+
+/// Marks a funded escrow completed so participants can withdraw. Callers are
+/// responsible for enforcing the settlement timelock first.
+fn settle_escrow(escrow_id: u64) -> String {
+    let (total_staked, _) = settlement_totals(escrow_id);
 
     let target_key = escrow_target_key(escrow_id);
     let target_storage_key = runtime::get_key(&target_key).unwrap_or_revert();
@@ -454,289 +995,5302 @@ pub extern "C" fn complete_escrow() {
         runtime::revert(casper_types::ApiError::User(105));
     }
 
+    complete_settlement(escrow_id)
+}
+
+/// Marks the escrow completed and pays out whatever it holds, without
+/// checking the target.
+fn complete_settlement(escrow_id: u64) -> String {
+    require_not_frozen();
+    // Settling twice would restart the unlock clock and repeat payouts.
     let completed_key = escrow_completed_key(escrow_id);
+    let is_completed: bool = read_named_value(&completed_key);
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(100));
+    }
+    let (total_staked, total_yield) = settlement_totals(escrow_id);
+    require_solvent(escrow_id, total_staked, total_yield);
+
     let completed_storage_key = runtime::get_key(&completed_key).unwrap_or_revert();
     let completed_uref = completed_storage_key.into_uref().unwrap_or_revert();
     storage::write(completed_uref, true);
+    set_status(escrow_id, STATUS_COMPLETED);
+    write_named_value(&escrow_completed_at_key(escrow_id), now_millis());
 
-    let total_yield_key = escrow_total_yield_key(escrow_id);
-    let total_yield_storage_key = runtime::get_key(&total_yield_key).unwrap_or_revert();
-    let total_yield_uref = total_yield_storage_key.into_uref().unwrap_or_revert();
-    let total_yield: u64 = storage::read(total_yield_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+    remove_from_creator_escrows(escrow_id);
 
     emit_event("escrow_completed", escrow_id, &format!("total_staked:{},total_yield:{}", total_staked, total_yield));
 
-    runtime::ret(
-        CLValue::from_t(format!("Escrow {} completed with {} staked and {} yield", escrow_id, total_staked, total_yield))
-            .unwrap_or_revert(),
-    );
-}
-
-#[no_mangle]
-pub extern "C" fn withdraw() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+    if runtime::has_key(&escrow_recipients_key(escrow_id)) {
+        settle_to_recipients(escrow_id, total_staked, total_yield);
+    } else if read_named_value_or(&escrow_settle_to_creator_key(escrow_id), false) {
+        if read_named_value_or(&escrow_grace_refund_key(escrow_id), false) {
+            let post_complete_grace: u64 = read_named_value_or(POST_COMPLETE_GRACE_KEY, 0u64);
+            let refundable_until = now_millis().saturating_add(post_complete_grace);
+            write_named_value(&escrow_refundable_until_key(escrow_id), refundable_until);
+            write_named_value(&escrow_payout_pending_key(escrow_id), true);
+
+            emit_event("creator_payout_deferred", escrow_id, &format!("refundable_until:{}", refundable_until));
+        } else {
+            settle_cspr_to_creator(escrow_id, total_staked, total_yield);
+        }
+    } else {
+        route_yield_dust(escrow_id, total_staked, total_yield);
+    }
 
-    let completed_key = escrow_completed_key(escrow_id);
-    let completed_storage_key = runtime::get_key(&completed_key).unwrap_or_revert();
-    let completed_uref = completed_storage_key.into_uref().unwrap_or_revert();
-    let is_completed: bool = storage::read(completed_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+    let total_refunded: u64 = read_named_value_or(TOTAL_REFUNDED_KEY, 0u64);
+    let total_settled: u64 = read_named_value_or(TOTAL_SETTLED_KEY, 0u64);
+    emit_event("ledger_snapshot", escrow_id, &format!("total_refunded:{},total_settled:{}", total_refunded, total_settled));
 
-    if !is_completed {
-        runtime::revert(casper_types::ApiError::User(101));
+    if read_named_value_or(&escrow_recurring_key(escrow_id), false) {
+        open_next_period(escrow_id);
     }
 
-    let withdrawn_key = escrow_withdrawn_key(escrow_id, participant);
-    match runtime::get_key(&withdrawn_key) {
-        Some(key) => {
-            let uref = key.into_uref().unwrap_or_revert();
-            let already_withdrawn: bool = storage::read(uref)
-                .unwrap_or_revert()
-                .unwrap_or_revert();
-            if already_withdrawn {
-                runtime::revert(casper_types::ApiError::User(102));
-            }
-        }
-        None => {}
+    format!("Escrow {} completed with {} staked and {} yield", escrow_id, total_staked, total_yield)
+}
+
+/// Principal and yield that settlement pays out, as `(total_staked, total_yield)`.
+fn settlement_totals(escrow_id: u64) -> (u64, u64) {
+    let total_staked: u64 = read_named_value(&escrow_total_staked_key(escrow_id));
+    let total_yield: u64 = read_named_value(&escrow_total_yield_key(escrow_id));
+    (total_staked, total_yield)
+}
+
+/// Reverts with `ERR_INSOLVENT` unless the escrow's purses actually hold the
+/// principal and yield its ledger is about to settle, so a corrupted ledger
+/// fails loudly before any transfer instead of partway through one.
+fn require_solvent(escrow_id: u64, total_staked: u64, total_yield: u64) {
+    if !is_solvent(escrow_id, total_staked, total_yield) {
+        runtime::revert(casper_types::ApiError::User(ERR_INSOLVENT));
     }
+}
 
-    let stake_key = escrow_stake_key(escrow_id, participant);
-    let stake_storage_key = runtime::get_key(&stake_key).unwrap_or_revert();
-    let stake_uref = stake_storage_key.into_uref().unwrap_or_revert();
-    let staked_amount: u64 = storage::read(stake_uref)
+/// Whether the escrow's purses hold at least `total_staked` principal and
+/// `total_yield` yield.
+fn is_solvent(escrow_id: u64, total_staked: u64, total_yield: u64) -> bool {
+    let yield_purse = runtime::get_key(&escrow_yield_purse_key(escrow_id))
         .unwrap_or_revert()
+        .into_uref()
         .unwrap_or_revert();
+    let principal_held = system::get_purse_balance(get_escrow_purse(escrow_id)).unwrap_or_revert();
+    let yield_held = system::get_purse_balance(yield_purse).unwrap_or_revert();
+    principal_held >= U512::from(total_staked) && yield_held >= U512::from(total_yield)
+}
 
-    if staked_amount == 0 {
-        runtime::revert(casper_types::ApiError::User(106));
+/// Reopens a completed recurring escrow under the next free id with the same
+/// creator and settings, and links the two. The successor takes no creation
+/// deposit; the completed escrow's deposit stays claimable as before.
+fn open_next_period(escrow_id: u64) {
+    let mut next_id = escrow_id.checked_add(1).unwrap_or_revert();
+    while runtime::has_key(&escrow_key(next_id)) {
+        next_id = next_id.checked_add(1).unwrap_or_revert();
     }
 
-    let total_staked_key = escrow_total_staked_key(escrow_id);
-    let total_staked_storage_key = runtime::get_key(&total_staked_key).unwrap_or_revert();
-    let total_staked_uref = total_staked_storage_key.into_uref().unwrap_or_revert();
-    let total_staked: u64 = storage::read(total_staked_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+    let creator: AccountHash = read_named_value(&escrow_creator_key(escrow_id));
+    copy_escrow(escrow_id, next_id, creator);
+    write_named_value(&escrow_recurring_key(next_id), true);
+    write_named_value(&escrow_next_key(escrow_id), next_id);
+    write_named_value(&escrow_prev_key(next_id), escrow_id);
 
-    let total_yield_key = escrow_total_yield_key(escrow_id);
-    let total_yield_storage_key = runtime::get_key(&total_yield_key).unwrap_or_revert();
-    let total_yield_uref = total_yield_storage_key.into_uref().unwrap_or_revert();
-    let total_yield: u64 = storage::read(total_yield_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+    emit_event("next_period_opened", escrow_id, &format!("next:{}", next_id));
+}
 
-    let participant_yield = if total_staked > 0 {
-        (staked_amount * total_yield) / total_staked
-    } else {
-        0u64
-    };
+fn remove_from_creator_escrows(escrow_id: u64) {
+    let creator_key = escrow_creator_key(escrow_id);
+    if runtime::has_key(&creator_key) {
+        let creator: AccountHash = read_named_value(&creator_key);
+        let creator_escrows_key = creator_escrows_key(creator);
+        let mut creator_escrows: Vec<u64> = read_named_value_or(&creator_escrows_key, Vec::new());
+        creator_escrows.retain(|id| *id != escrow_id);
+        write_named_value(&creator_escrows_key, creator_escrows);
+    }
+}
 
-    let participant_yield_key = escrow_participant_yield_key(escrow_id, participant);
-    let participant_yield_uref = storage::new_uref(participant_yield);
-    runtime::put_key(&participant_yield_key, participant_yield_uref.into());
+fn add_to_account_escrows(account: AccountHash, escrow_id: u64) {
+    let account_escrows_key = account_escrows_key(account);
+    let mut account_escrows: Vec<u64> = read_named_value_or(&account_escrows_key, Vec::new());
+    if !account_escrows.contains(&escrow_id) {
+        account_escrows.push(escrow_id);
+        write_named_value(&account_escrows_key, account_escrows);
+    }
+}
 
-    let target_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+fn remove_from_account_escrows(account: AccountHash, escrow_id: u64) {
+    let account_escrows_key = account_escrows_key(account);
+    let mut account_escrows: Vec<u64> = read_named_value_or(&account_escrows_key, Vec::new());
+    account_escrows.retain(|id| *id != escrow_id);
+    write_named_value(&account_escrows_key, account_escrows);
+}
 
-    let escrow_purse_key = escrow_purse_key(escrow_id);
-    let escrow_purse_storage_key = runtime::get_key(&escrow_purse_key).unwrap_or_revert();
-    let escrow_purse = escrow_purse_storage_key.into_uref().unwrap_or_revert();
+/// Reverts unless `tags` are at most `MAX_TAGS_PER_ESCROW` distinct,
+/// non-empty strings of at most `MAX_TAG_LENGTH` bytes.
+fn require_valid_tags(tags: &[String]) {
+    let invalid = tags.len() > MAX_TAGS_PER_ESCROW
+        || tags.iter().any(|tag| tag.is_empty() || tag.len() > MAX_TAG_LENGTH)
+        || tags.iter().enumerate().any(|(i, tag)| tags[..i].contains(tag));
+    if invalid {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_TAGS));
+    }
+}
 
-    system::transfer_from_purse_to_purse(
-        escrow_purse,
-        target_purse,
-        U512::from(staked_amount),
-        None
-    ).unwrap_or_revert();
+/// Stores the escrow's tags and adds it to each tag's index.
+fn index_tags(escrow_id: u64, tags: Vec<String>) {
+    if tags.is_empty() {
+        return;
+    }
+    for tag in &tags {
+        let tag_key = tag_escrows_key(tag);
+        let mut tag_escrows: Vec<u64> = read_named_value_or(&tag_key, Vec::new());
+        tag_escrows.push(escrow_id);
+        write_named_value(&tag_key, tag_escrows);
+    }
+    write_named_value(&escrow_tags_key(escrow_id), tags);
+}
 
-    if participant_yield > 0 {
-        let yield_purse_key = escrow_yield_purse_key(escrow_id);
-        let yield_purse_storage_key = runtime::get_key(&yield_purse_key).unwrap_or_revert();
-        let yield_purse = yield_purse_storage_key.into_uref().unwrap_or_revert();
+/// Drops the escrow from the index of every tag it carries.
+fn remove_from_tag_escrows(escrow_id: u64) {
+    let tags: Vec<String> = read_named_value_or(&escrow_tags_key(escrow_id), Vec::new());
+    for tag in tags {
+        let tag_key = tag_escrows_key(&tag);
+        let mut tag_escrows: Vec<u64> = read_named_value_or(&tag_key, Vec::new());
+        tag_escrows.retain(|id| *id != escrow_id);
+        write_named_value(&tag_key, tag_escrows);
+    }
+}
 
-        system::transfer_from_purse_to_purse(
-            yield_purse,
-            target_purse,
-            U512::from(participant_yield),
+/// Moves the pooled principal and any deposited yield from the escrow's
+/// purses to the creator's settlement purse if one was set, otherwise their
+/// account, less any gas rebates paid to participants and the charity's
+/// share.
+fn settle_cspr_to_creator(escrow_id: u64, total_staked: u64, total_yield: u64) {
+    let creator: AccountHash = read_named_value(&escrow_creator_key(escrow_id));
+    let escrow_purse = get_escrow_purse(escrow_id);
+
+    let mut rebates_paid = 0u64;
+    for (participant, rebate) in gas_rebates(escrow_id) {
+        system::transfer_from_purse_to_account(
+            escrow_purse,
+            participant,
+            U512::from(rebate),
             None
-        ).unwrap_or_revert();
+        ).unwrap_or_revert_with(casper_types::ApiError::User(ERR_PAYOUT_TRANSFER_FAILED));
+        rebates_paid += rebate;
     }
+    if rebates_paid > 0 {
+        write_named_value(&escrow_gas_rebates_paid_key(escrow_id), rebates_paid);
+        emit_event("gas_rebates_paid", escrow_id, &format!("amount:{}", rebates_paid));
+    }
+    let principal = total_staked - rebates_paid;
+    let (principal_to_creator, yield_to_creator) = creator_net(escrow_id, total_staked, total_yield);
+
+    let settlement_purse = runtime::get_key(&escrow_settlement_purse_key(escrow_id))
+        .and_then(|key| key.into_uref());
+    let pay_creator = |source: casper_types::URef, amount: u64| {
+        match settlement_purse {
+            Some(settlement_purse) => system::transfer_from_purse_to_purse(
+                source,
+                settlement_purse,
+                U512::from(amount),
+                None
+            ),
+            None => system::transfer_from_purse_to_account(
+                source,
+                creator,
+                U512::from(amount),
+                None
+            ).map(|_| ()),
+        }
+        .unwrap_or_revert_with(casper_types::ApiError::User(ERR_PAYOUT_TRANSFER_FAILED));
+    };
 
-    let withdrawn_uref = storage::new_uref(true);
-    runtime::put_key(&withdrawn_key, withdrawn_uref.into());
+    pay_charity(escrow_id, escrow_purse, principal - principal_to_creator);
+    if principal_to_creator > 0 {
+        pay_creator(escrow_purse, principal_to_creator);
+    }
 
-    let liquid_balance_key = escrow_liquid_balance_key(escrow_id, participant);
-    match runtime::get_key(&liquid_balance_key) {
-        Some(key) => {
-            let uref = key.into_uref().unwrap_or_revert();
-            storage::write(uref, 0u64);
+    if total_yield > 0 {
+        let yield_purse = runtime::get_key(&escrow_yield_purse_key(escrow_id))
+            .unwrap_or_revert()
+            .into_uref()
+            .unwrap_or_revert();
+        pay_charity(escrow_id, yield_purse, total_yield - yield_to_creator);
+        if yield_to_creator > 0 {
+            pay_creator(yield_purse, yield_to_creator);
         }
-        None => {}
     }
 
-    let total_withdrawal = staked_amount + participant_yield;
+    let total_settled = add_to_ledger_total(TOTAL_SETTLED_KEY, total_staked + total_yield);
 
-    emit_event("withdrawn", escrow_id, &format!("participant:{},principal:{},yield:{},total:{}", participant, staked_amount, participant_yield, total_withdrawal));
+    emit_event("settled_to_creator", escrow_id, &format!("creator:{},principal:{},yield:{},total_settled:{}", creator, principal, total_yield, total_settled));
+}
 
-    runtime::ret(
-        CLValue::from_t(format!("Withdrawn {} (principal: {}, yield: {}) from escrow {}", total_withdrawal, staked_amount, participant_yield, escrow_id))
-            .unwrap_or_revert(),
-    );
+/// The escrow charity's `charity_bps` share of `proceeds`, rounded down. 0
+/// for escrows without a charity.
+fn charity_share(escrow_id: u64, proceeds: u64) -> u64 {
+    let charity_key = escrow_charity_key(escrow_id);
+    if !runtime::has_key(&charity_key) {
+        return 0;
+    }
+    let (_, charity_bps): (AccountHash, u64) = read_named_value(&charity_key);
+    (u128::from(proceeds) * u128::from(charity_bps) / u128::from(BPS_DENOMINATOR)) as u64
 }
 
-#[no_mangle]
-pub extern "C" fn get_participants() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+/// What a settle-to-creator payout leaves the creator as `(principal,
+/// yield)`: gas rebates come off the principal, then the charity takes its
+/// share of each.
+fn creator_net(escrow_id: u64, total_staked: u64, total_yield: u64) -> (u64, u64) {
+    let rebates: u64 = gas_rebates(escrow_id).iter().map(|(_, rebate)| rebate).sum();
+    let principal = total_staked - rebates;
+    (
+        principal - charity_share(escrow_id, principal),
+        total_yield - charity_share(escrow_id, total_yield),
+    )
+}
 
-    let participants_key = escrow_participants_key(escrow_id);
-    let participants_storage_key = runtime::get_key(&participants_key).unwrap_or_revert();
-    let participants_uref = participants_storage_key.into_uref().unwrap_or_revert();
-    let participants: Vec<AccountHash> = storage::read(participants_uref)
-        .unwrap_or_revert()
-        .unwrap_or_revert();
+/// Sends `donation` from `purse` to the escrow's charity.
+fn pay_charity(escrow_id: u64, purse: casper_types::URef, donation: u64) {
+    if donation > 0 {
+        let (charity, _): (AccountHash, u64) = read_named_value(&escrow_charity_key(escrow_id));
+        system::transfer_from_purse_to_account(
+            purse,
+            charity,
+            U512::from(donation),
+            None
+        ).unwrap_or_revert_with(casper_types::ApiError::User(ERR_PAYOUT_TRANSFER_FAILED));
 
-    runtime::ret(
-        CLValue::from_t(participants)
-            .unwrap_or_revert(),
-    );
+        emit_event("charity_paid", escrow_id, &format!("charity:{},amount:{}", charity, donation));
+    }
 }
 
-#[no_mangle]
-pub extern "C" fn get_stake() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+/// Gas rebates owed on settlement as `(participant, rebate)` pairs, one per
+/// participant holding stake. Each rebate is capped at that participant's
+/// stake, so together they never exceed the principal.
+fn gas_rebates(escrow_id: u64) -> Vec<(AccountHash, u64)> {
+    let gas_rebate: u64 = read_named_value_or(&escrow_gas_rebate_key(escrow_id), 0u64);
+    if gas_rebate == 0 {
+        return Vec::new();
+    }
 
-    let stake_key = escrow_stake_key(escrow_id, participant);
-    let stake_storage_key = runtime::get_key(&stake_key).unwrap_or_revert();
-    let stake_uref = stake_storage_key.into_uref().unwrap_or_revert();
-    let staked_amount: u64 = storage::read(stake_uref)
+    let participants: Vec<AccountHash> = read_named_value(&escrow_participants_key(escrow_id));
+    participants
+        .into_iter()
+        .filter_map(|participant| {
+            let stake: u64 = read_named_value_or(&escrow_stake_key(escrow_id, participant), 0u64);
+            let rebate = gas_rebate.min(stake);
+            (rebate > 0).then_some((participant, rebate))
+        })
+        .collect()
+}
+
+/// Splits the pooled principal and yield between the escrow's recipients in
+/// proportion to their scheduled amounts.
+fn settle_to_recipients(escrow_id: u64, total_staked: u64, total_yield: u64) {
+    let recipients: Vec<(AccountHash, u64)> = read_named_value(&escrow_recipients_key(escrow_id));
+    let target_amount: u64 = read_named_value(&escrow_target_key(escrow_id));
+
+    let escrow_purse = get_escrow_purse(escrow_id);
+    let principal_donated = charity_share(escrow_id, total_staked);
+    pay_charity(escrow_id, escrow_purse, principal_donated);
+    pay_pro_rata(escrow_purse, total_staked - principal_donated, &recipients, target_amount);
+    if total_yield > 0 {
+        let yield_purse = runtime::get_key(&escrow_yield_purse_key(escrow_id))
+            .unwrap_or_revert()
+            .into_uref()
+            .unwrap_or_revert();
+        let yield_donated = charity_share(escrow_id, total_yield);
+        pay_charity(escrow_id, yield_purse, yield_donated);
+        pay_pro_rata(yield_purse, total_yield - yield_donated, &recipients, target_amount);
+    }
+
+    let total_settled = add_to_ledger_total(TOTAL_SETTLED_KEY, total_staked + total_yield);
+
+    emit_event("settled_to_recipients", escrow_id, &format!("recipients:{},principal:{},yield:{},total_settled:{}", recipients.len(), total_staked, total_yield, total_settled));
+}
+
+/// Pays `pool` out of `purse` weighted by each recipient's amount over
+/// `weight_total`. Rounding dust goes to the last recipient so the shares
+/// always add up to `pool`.
+fn pay_pro_rata(purse: casper_types::URef, pool: u64, recipients: &[(AccountHash, u64)], weight_total: u64) {
+    let mut remaining = pool;
+    for (index, (recipient, weight)) in recipients.iter().enumerate() {
+        let share = if index + 1 == recipients.len() {
+            remaining
+        } else {
+            ((pool as u128 * *weight as u128) / weight_total as u128) as u64
+        };
+        remaining -= share;
+
+        if share > 0 {
+            system::transfer_from_purse_to_account(
+                purse,
+                *recipient,
+                U512::from(share),
+                None
+            ).unwrap_or_revert_with(casper_types::ApiError::User(ERR_PAYOUT_TRANSFER_FAILED));
+        }
+    }
+}
+
+/// For escrows whose participants withdraw their own stake, sends the yield
+/// that rounding down `stake * total_yield / total_staked` leaves unclaimed
+/// to the dust recipient. Yield is frozen at completion, so withdrawals and
+/// the dust together add up to exactly `total_yield`.
+fn route_yield_dust(escrow_id: u64, total_staked: u64, total_yield: u64) {
+    if total_yield == 0 || total_staked == 0 {
+        return;
+    }
+
+    let participants: Vec<AccountHash> = read_named_value(&escrow_participants_key(escrow_id));
+    let claimable = participants.iter().fold(0u64, |sum, participant| {
+        let stake: u64 = read_named_value_or(&escrow_stake_key(escrow_id, *participant), 0u64);
+        sum + ((stake as u128 * total_yield as u128) / total_staked as u128) as u64
+    });
+    let dust = total_yield - claimable;
+    write_named_value(&escrow_dust_key(escrow_id), dust);
+    if dust == 0 {
+        return;
+    }
+
+    let creator: AccountHash = read_named_value(&escrow_creator_key(escrow_id));
+    let dust_recipient: AccountHash = read_named_value_or(DUST_RECIPIENT_KEY, creator);
+    let yield_purse = runtime::get_key(&escrow_yield_purse_key(escrow_id))
         .unwrap_or_revert()
+        .into_uref()
         .unwrap_or_revert();
+    system::transfer_from_purse_to_account(
+        yield_purse,
+        dust_recipient,
+        U512::from(dust),
+        None
+    ).unwrap_or_revert_with(casper_types::ApiError::User(ERR_PAYOUT_TRANSFER_FAILED));
 
-    runtime::ret(
-        CLValue::from_t(staked_amount)
-            .unwrap_or_revert(),
-    );
+    let total_settled = add_to_ledger_total(TOTAL_SETTLED_KEY, dust);
+
+    emit_event("dust_routed", escrow_id, &format!("recipient:{},amount:{},total_settled:{}", dust_recipient, dust, total_settled));
 }
 
-#[no_mangle]
-pub extern "C" fn get_liquid_balance() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+/// Block time from which a completed escrow's stakes may be withdrawn, or 0
+/// if it has not completed yet.
+fn unlock_time(escrow_id: u64) -> u64 {
+    let completed_at_key = escrow_completed_at_key(escrow_id);
+    if !runtime::has_key(&completed_at_key) {
+        return 0;
+    }
+    let completed_at: u64 = read_named_value(&completed_at_key);
+    let min_stake_duration: u64 = read_named_value_or(&escrow_min_stake_duration_key(escrow_id), 0u64);
+    completed_at.saturating_add(min_stake_duration)
+}
 
-    let liquid_balance_key = escrow_liquid_balance_key(escrow_id, participant);
-    let liquid_storage_key = runtime::get_key(&liquid_balance_key).unwrap_or_revert();
-    let liquid_uref = liquid_storage_key.into_uref().unwrap_or_revert();
-    let liquid_amount: u64 = storage::read(liquid_uref)
+/// Whether settlement sends the pool to the creator or recipients, leaving
+/// nothing for participants to withdraw.
+fn pays_out_on_settlement(escrow_id: u64) -> bool {
+    runtime::has_key(&escrow_recipients_key(escrow_id))
+        || read_named_value_or(&escrow_settle_to_creator_key(escrow_id), false)
+}
+
+/// The later of the settlement timelock and the contract's
+/// `min_settle_delay_after_last_join` counted from the last new participant.
+fn earliest_settle_time(escrow_id: u64) -> u64 {
+    let settle_after: u64 = read_named_value_or(&escrow_settle_after_key(escrow_id), 0u64);
+    let min_settle_delay: u64 = read_named_value_or(MIN_SETTLE_DELAY_AFTER_LAST_JOIN_KEY, 0u64);
+    let last_join_time: u64 = read_named_value_or(&escrow_last_join_time_key(escrow_id), 0u64);
+    if min_settle_delay == 0 || last_join_time == 0 {
+        return settle_after;
+    }
+    settle_after.max(last_join_time.saturating_add(min_settle_delay))
+}
+
+/// Reverts while a dispute is open, the settlement delay has not elapsed or,
+/// for cosigned escrows, either signer has not approved yet.
+fn require_settlement_unlocked(escrow_id: u64) {
+    if let Some(reason) = settlement_blocker(escrow_id) {
+        runtime::revert(casper_types::ApiError::User(reason));
+    }
+}
+
+/// The error code settling the escrow would currently revert with, checked
+/// in order: a batch refund in progress, a dispute, the settlement timelock
+/// or post-join wait, an unmet oracle condition, or missing cosigner
+/// approvals. `None` if none apply.
+fn settlement_blocker(escrow_id: u64) -> Option<u16> {
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status == STATUS_REFUNDING {
+        return Some(ERR_REFUND_IN_PROGRESS);
+    }
+    if status == STATUS_DISPUTED {
+        return Some(ERR_ESCROW_DISPUTED);
+    }
+
+    if now_millis() < earliest_settle_time(escrow_id) {
+        return Some(ERR_SETTLEMENT_LOCKED);
+    }
+
+    if runtime::has_key(&escrow_oracle_key(escrow_id))
+        && !read_named_value_or(&escrow_condition_met_key(escrow_id), false)
+    {
+        return Some(ERR_CONDITION_NOT_MET);
+    }
+
+    if runtime::has_key(&escrow_cosigner_key(escrow_id)) {
+        let creator_approved: bool = read_named_value_or(&escrow_creator_approved_key(escrow_id), false);
+        let cosigner_approved: bool = read_named_value_or(&escrow_cosigner_approved_key(escrow_id), false);
+        if !creator_approved || !cosigner_approved {
+            return Some(ERR_SETTLEMENT_NOT_APPROVED);
+        }
+    }
+
+    None
+}
+
+/// Returns the approval flag key for the caller if they are the creator or
+/// cosigner of a cosigned escrow, reverting otherwise.
+fn require_signer(escrow_id: u64) -> String {
+    let caller = runtime::get_caller();
+    let cosigner_key = escrow_cosigner_key(escrow_id);
+    if !runtime::has_key(&cosigner_key) {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_SIGNER));
+    }
+
+    let creator: AccountHash = read_named_value(&escrow_creator_key(escrow_id));
+    let cosigner: AccountHash = read_named_value(&cosigner_key);
+    if caller == creator {
+        escrow_creator_approved_key(escrow_id)
+    } else if caller == cosigner {
+        escrow_cosigner_approved_key(escrow_id)
+    } else {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_SIGNER))
+    }
+}
+
+/// Emits an event that is not tied to a particular escrow, such as admin changes.
+fn emit_contract_event(event_name: &str, data: &str) {
+    if !events_enabled() {
+        return;
+    }
+
+    let counter: u64 = read_named_value_or(CONTRACT_EVENT_COUNTER_KEY, 0u64) + 1;
+    write_named_value(CONTRACT_EVENT_COUNTER_KEY, counter);
+
+    let event_key = format!("event_{}_{}", event_name, counter);
+    let event_uref = storage::new_uref(data.to_string());
+    runtime::put_key(&event_key, event_uref.into());
+}
+
+/// Accounts that may hold principal in the escrow purse: participants plus
+/// installment payers that have not reached the split.
+fn principal_holders(escrow_id: u64) -> Vec<AccountHash> {
+    let mut holders: Vec<AccountHash> = read_named_value(&escrow_participants_key(escrow_id));
+    let payers: Vec<AccountHash> = read_named_value_or(&escrow_payers_key(escrow_id), Vec::new());
+    for payer in payers {
+        if !holders.contains(&payer) {
+            holders.push(payer);
+        }
+    }
+    holders
+}
+
+/// Sends every principal holder their stake plus any installments below the
+/// split, then marks the escrow cancelled. Returns the amount refunded and the
+/// new contract-wide refund total.
+fn cancel_with_full_refund(escrow_id: u64) -> (u64, u64) {
+    let total_refund = refund_principal(escrow_id, &principal_holders(escrow_id));
+    mark_cancelled(escrow_id);
+
+    let total_refunded = add_to_ledger_total(TOTAL_REFUNDED_KEY, total_refund);
+
+    (total_refund, total_refunded)
+}
+
+/// Sends each of `holders` their stake plus any installments below the split
+/// and zeroes their balances. Returns the total sent.
+fn refund_principal(escrow_id: u64, holders: &[AccountHash]) -> u64 {
+    let escrow_purse = get_escrow_purse(escrow_id);
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let mut total_refund = 0u64;
+    for participant in holders.iter().copied() {
+        let refund_amount = principal_owed(escrow_id, participant, split);
+        if refund_amount == 0 {
+            continue;
+        }
+
+        system::transfer_from_purse_to_account(
+            escrow_purse,
+            refund_address(escrow_id, participant),
+            U512::from(refund_amount),
+            None
+        ).unwrap_or_revert();
+
+        write_named_value(&escrow_stake_key(escrow_id, participant), 0u64);
+        write_named_value(&escrow_paid_key(escrow_id, participant), 0u64);
+        write_named_value(&escrow_liquid_balance_key(escrow_id, participant), 0u64);
+        total_refund += refund_amount;
+    }
+    total_refund
+}
+
+/// Principal a refund would return to `participant`: their stake plus any
+/// installments still below the split.
+fn principal_owed(escrow_id: u64, participant: AccountHash, split: u64) -> u64 {
+    let staked_amount: u64 = read_named_value_or(&escrow_stake_key(escrow_id, participant), 0u64);
+    let paid: u64 = read_named_value_or(&escrow_paid_key(escrow_id, participant), 0u64);
+    let partial_amount = if paid >= split { 0u64 } else { paid };
+    staked_amount + partial_amount
+}
+
+/// Where the participant's refunds go: their chosen refund address, or the
+/// participant themselves.
+fn refund_address(escrow_id: u64, participant: AccountHash) -> AccountHash {
+    read_named_value_or(&escrow_refund_address_key(escrow_id, participant), participant)
+}
+
+/// Closes an escrow whose principal has been refunded in full.
+fn mark_cancelled(escrow_id: u64) {
+    write_named_value(&escrow_total_staked_key(escrow_id), 0u64);
+    write_named_value(&escrow_joined_count_key(escrow_id), 0u64);
+    write_named_value(&escrow_completed_key(escrow_id), true);
+    set_status(escrow_id, STATUS_CANCELLED);
+    remove_from_creator_escrows(escrow_id);
+}
+
+/// Completed, cancelled, or still open past a deadline it did not fill by.
+/// Funded or disputed escrows past their deadline can still settle.
+fn is_terminal(escrow_id: u64) -> bool {
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status == STATUS_COMPLETED || status == STATUS_CANCELLED {
+        return true;
+    }
+    let deadline: u64 = read_named_value_or(&escrow_deadline_key(escrow_id), 0u64);
+    status == STATUS_OPEN && deadline > 0 && now_millis() >= deadline
+}
+
+/// Empties the escrow's deposit purse into `recipient` and returns the amount.
+fn release_deposit(escrow_id: u64, recipient: AccountHash) -> u64 {
+    let deposit_key = escrow_deposit_key(escrow_id);
+    let deposit: u64 = read_named_value_or(&deposit_key, 0u64);
+    if deposit == 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_NO_DEPOSIT));
+    }
+
+    let deposit_purse = runtime::get_key(&escrow_deposit_purse_key(escrow_id))
         .unwrap_or_revert()
+        .into_uref()
         .unwrap_or_revert();
+    system::transfer_from_purse_to_account(
+        deposit_purse,
+        recipient,
+        U512::from(deposit),
+        None
+    ).unwrap_or_revert();
 
-    runtime::ret(
-        CLValue::from_t(liquid_amount)
-            .unwrap_or_revert(),
-    );
+    write_named_value(&deposit_key, 0u64);
+    deposit
 }
 
-#[no_mangle]
-pub extern "C" fn get_yield() {
-    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+/// Portion of `participant`'s refund of `amount` kept as the leave fee.
+fn leave_fee(escrow_id: u64, participant: AccountHash, amount: u64) -> u64 {
+    let leave_fee_bps = leave_fee_bps(escrow_id, participant);
+    ((amount as u128 * leave_fee_bps as u128) / BPS_DENOMINATOR as u128) as u64
+}
 
-    let stake_key = escrow_stake_key(escrow_id, participant);
-    let stake_storage_key = runtime::get_key(&stake_key).unwrap_or_revert();
-    let stake_uref = stake_storage_key.into_uref().unwrap_or_revert();
-    let staked_amount: u64 = storage::read(stake_uref)
-.unwrap_or_revert()
-.unwrap_or_revert();
-let total_staked_key = escrow_total_staked_key(escrow_id);
-let total_staked_storage_key = runtime::get_key(&total_staked_key).unwrap_or_revert();
-let total_staked_uref = total_staked_storage_key.into_uref().unwrap_or_revert();
-let total_staked: u64 = storage::read(total_staked_uref)
-    .unwrap_or_revert()
-    .unwrap_or_revert();
+/// Leave fee rate for `participant` right now, in basis points. Flat at
+/// `leave_fee_bps` unless the contract has a higher late leave fee and the
+/// escrow a deadline, in which case it grows linearly with the share of the
+/// time from their join to the deadline that has passed. Nothing is charged
+/// once the deadline has passed: the escrow expired, nobody left early.
+fn leave_fee_bps(escrow_id: u64, participant: AccountHash) -> u64 {
+    let deadline: u64 = read_named_value_or(&escrow_deadline_key(escrow_id), 0u64);
+    if deadline > 0 && now_millis() >= deadline {
+        return 0;
+    }
+    let leave_fee_bps: u64 = read_named_value_or(LEAVE_FEE_BPS_KEY, 0u64);
+    let late_leave_fee_bps: u64 = read_named_value_or(LATE_LEAVE_FEE_BPS_KEY, 0u64);
+    if late_leave_fee_bps <= leave_fee_bps || deadline == 0 {
+        return leave_fee_bps;
+    }
 
-let total_yield_key = escrow_total_yield_key(escrow_id);
-let total_yield_storage_key = runtime::get_key(&total_yield_key).unwrap_or_revert();
-let total_yield_uref = total_yield_storage_key.into_uref().unwrap_or_revert();
-let total_yield: u64 = storage::read(total_yield_uref)
-    .unwrap_or_revert()
-    .unwrap_or_revert();
+    let created_at: u64 = read_named_value_or(&escrow_created_at_key(escrow_id), 0u64);
+    let join_time: u64 = read_named_value_or(&escrow_join_time_key(escrow_id, participant), created_at);
+    if deadline <= join_time {
+        return late_leave_fee_bps;
+    }
+    let held_for = now_millis().saturating_sub(join_time).min(deadline - join_time);
+    let increase = (late_leave_fee_bps - leave_fee_bps) as u128 * held_for as u128 / (deadline - join_time) as u128;
+    leave_fee_bps + increase as u64
+}
 
-let participant_yield = if total_staked > 0 {
-    (staked_amount * total_yield) / total_staked
-} else {
-    0u64
-};
+/// Records `participant`'s join time unless they already have one.
+fn record_join_time(escrow_id: u64, participant: AccountHash) {
+    let join_time_key = escrow_join_time_key(escrow_id, participant);
+    if !runtime::has_key(&join_time_key) {
+        write_named_value(&join_time_key, now_millis());
+    }
+}
 
-runtime::ret(
-    CLValue::from_t(participant_yield)
-        .unwrap_or_revert(),
-);
+/// Adds `amount` to one of the contract-wide ledger totals and returns the new total.
+fn add_to_ledger_total(total_key: &str, amount: u64) -> u64 {
+    let current: u64 = read_named_value_or(total_key, 0u64);
+    let updated = current
+        .checked_add(amount)
+        .unwrap_or_revert_with(casper_types::ApiError::User(ERR_LEDGER_OVERFLOW));
+    write_named_value(total_key, updated);
+    updated
 }
-#[no_mangle]
-pub extern "C" fn get_escrow_balance() {
-let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-let escrow_purse_key = escrow_purse_key(escrow_id);
-let escrow_purse_storage_key = runtime::get_key(&escrow_purse_key).unwrap_or_revert();
-let escrow_purse = escrow_purse_storage_key.into_uref().unwrap_or_revert();
-let balance = system::get_purse_balance(escrow_purse).unwrap_or_revert();
-let balance_u64: u64 = balance.as_u64();
 
-runtime::ret(
-    CLValue::from_t(balance_u64)
-        .unwrap_or_revert(),
-);
+/// Reverts while a batch refund is draining the escrow, so no new principal
+/// arrives behind the refund cursor.
+fn require_not_refunding(escrow_id: u64) {
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status == STATUS_REFUNDING {
+        runtime::revert(casper_types::ApiError::User(ERR_REFUND_IN_PROGRESS));
+    }
 }
-#[no_mangle]
-pub extern "C" fn get_participant_yield() {
-let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
-let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
-let participant_yield_key = escrow_participant_yield_key(escrow_id, participant);
 
-match runtime::get_key(&participant_yield_key) {
-    Some(key) => {
-        let uref = key.into_uref().unwrap_or_revert();
-        let yield_amount: u64 = storage::read(uref)
-            .unwrap_or_revert()
-            .unwrap_or_revert();
-        
-        runtime::ret(
-            CLValue::from_t(yield_amount)
-                .unwrap_or_revert(),
+/// Reverts with the first reason `account` may not join or pay into the escrow.
+fn require_can_join(escrow_id: u64, account: AccountHash) {
+    if let Some(reason) = join_blocker(escrow_id, account) {
+        runtime::revert(casper_types::ApiError::User(reason));
+    }
+}
+
+/// The error code a join or installment by `account` would currently revert
+/// with, checked in order: the contract frozen, a batch refund in progress,
+/// the escrow paused,
+/// its deadline passed, `account` an excluded creator, for NFT-gated
+/// escrows `account` holding no token of the gating CEP-78 contract as
+/// reported by its `balance_of`, or `account` not verified by the
+/// contract's verifier. `None` if none apply.
+fn join_blocker(escrow_id: u64, account: AccountHash) -> Option<u16> {
+    if read_named_value_or(FROZEN_KEY, false) {
+        return Some(ERR_FROZEN);
+    }
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status == STATUS_REFUNDING {
+        return Some(ERR_REFUND_IN_PROGRESS);
+    }
+    if read_named_value_or(&escrow_paused_key(escrow_id), false) {
+        return Some(ERR_ESCROW_PAUSED);
+    }
+    // Past its deadline an escrow is heading to settlement or refund, so it
+    // takes no new money. Completed escrows report their own error instead.
+    let deadline: u64 = read_named_value_or(&escrow_deadline_key(escrow_id), 0u64);
+    if deadline > 0 && now_millis() >= deadline && status != STATUS_COMPLETED {
+        return Some(ERR_ESCROW_EXPIRED);
+    }
+
+    let exclude_creator: bool = read_named_value_or(&escrow_exclude_creator_key(escrow_id), false);
+    if exclude_creator {
+        let creator: AccountHash = read_named_value(&escrow_creator_key(escrow_id));
+        if account == creator {
+            return Some(ERR_CREATOR_EXCLUDED);
+        }
+    }
+
+    let gating_nft_key = escrow_gating_nft_key(escrow_id);
+    if runtime::has_key(&gating_nft_key) {
+        let gating_nft: ContractHash = read_named_value(&gating_nft_key);
+        let balance: u64 = runtime::call_contract(
+            gating_nft,
+            EP_NFT_BALANCE_OF,
+            runtime_args! { ARG_NFT_TOKEN_OWNER => Key::Account(account) },
         );
+        if balance == 0 {
+            return Some(ERR_NOT_NFT_HOLDER);
+        }
     }
-    None => {
-        runtime::ret(
-            CLValue::from_t(0u64)
-                .unwrap_or_revert(),
+
+    if runtime::has_key(VERIFIER_KEY) {
+        let verifier: ContractHash = read_named_value(VERIFIER_KEY);
+        let verified: bool = runtime::call_contract(
+            verifier,
+            EP_VERIFIER_IS_VERIFIED,
+            runtime_args! { ARG_ACCOUNT => Key::Account(account) },
         );
+        if !verified {
+            return Some(ERR_NOT_VERIFIED);
+        }
+    }
+
+    None
+}
+
+/// Records the caller's optional `idempotency_key` for this escrow and returns
+/// true if it had already been used, meaning the call is a retry and must not
+/// take effect again. Clients should use a fresh random key per intent.
+fn is_retried_call(escrow_id: u64, caller: AccountHash) -> bool {
+    let idempotency_key: String = match runtime::try_get_named_arg(ARG_IDEMPOTENCY_KEY) {
+        Some(idempotency_key) => idempotency_key,
+        None => return false,
+    };
+    if idempotency_key.is_empty() || idempotency_key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_IDEMPOTENCY_KEY));
     }
+
+    let seen_key = escrow_idempotency_key(escrow_id, caller, &idempotency_key);
+    if runtime::has_key(&seen_key) {
+        return true;
+    }
+    write_named_value(&seen_key, true);
+    false
 }
+
+/// Copies a per-escrow setting from `source_id` to `escrow_id`, if the source has it.
+fn copy_escrow_value<T: CLTyped + FromBytes + ToBytes>(key: fn(u64) -> String, source_id: u64, escrow_id: u64) {
+    let source_key = key(source_id);
+    if runtime::has_key(&source_key) {
+        let value: T = read_named_value(&source_key);
+        write_named_value(&key(escrow_id), value);
+    }
 }
-#[no_mangle]
-pub extern "C" fn get_contract_purse() {
-let purse = get_main_purse();
-runtime::ret(
-    CLValue::from_t(purse)
-        .unwrap_or_revert(),
+
+fn require_memo_length(memo: &str) {
+    if memo.len() > MAX_MEMO_LENGTH {
+        runtime::revert(casper_types::ApiError::User(ERR_MEMO_TOO_LONG));
+    }
+}
+
+fn require_creator(escrow_id: u64) {
+    let creator: AccountHash = read_named_value(&escrow_creator_key(escrow_id));
+    if runtime::get_caller() != creator {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_CREATOR));
+    }
+}
+
+/// Pause and unpause are open to the creator and the escrow admin.
+fn require_creator_or_escrow_admin(escrow_id: u64) {
+    let caller = runtime::get_caller();
+    let escrow_admin_key = escrow_admin_key(escrow_id);
+    if runtime::has_key(&escrow_admin_key) {
+        let escrow_admin: AccountHash = read_named_value(&escrow_admin_key);
+        if caller == escrow_admin {
+            return;
+        }
+    }
+    require_creator(escrow_id);
+}
+
+fn require_admin() {
+    let admin: AccountHash = read_named_value(ADMIN_KEY);
+    if runtime::get_caller() != admin {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_ADMIN));
+    }
+}
+
+fn require_not_frozen() {
+    if read_named_value_or(FROZEN_KEY, false) {
+        runtime::revert(casper_types::ApiError::User(ERR_FROZEN));
+    }
+}
+
+/// Installer-account key name for one deployment: `{prefix}_{name}`, or just
+/// `name` when no prefix was given.
+fn prefixed_key(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}_{}", prefix, name)
+    }
+}
+
+/// Renders one line per entry point, `name(arg: type, ...) -> type`, sorted by name.
+fn describe_entry_points(entry_points: &EntryPoints) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for name in entry_points.keys() {
+        let entry_point = entry_points.get(name).unwrap_or_revert();
+        let args: Vec<String> = entry_point
+            .args()
+            .iter()
+            .map(|arg| format!("{}: {}", arg.name(), arg.cl_type()))
+            .collect();
+        lines.push(format!("{}({}) -> {}", name, args.join(", "), entry_point.ret()));
+    }
+    lines.join("\n")
+}
+
+/// Parameters shared by `create_escrow` and `create_recurring_escrow`.
+fn create_escrow_params() -> Vec<Parameter> {
+    vec![
+        Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        Parameter::new(ARG_AMOUNT, CLType::U64),
+        Parameter::new(ARG_TARGET_AMOUNT, CLType::U64),
+        Parameter::new(ARG_DEADLINE, CLType::U64),
+        Parameter::new(ARG_EXCLUDE_CREATOR, CLType::Bool),
+        Parameter::new(ARG_MEMO, CLType::String),
+        Parameter::new(ARG_COSIGNER, CLType::Key),
+        Parameter::new(ARG_ESCROW_ADMIN, CLType::Key),
+        Parameter::new(ARG_GATING_NFT, CLType::ByteArray(32)),
+        Parameter::new(ARG_SETTLE_TO_CREATOR, CLType::Bool),
+        Parameter::new(ARG_SETTLE_PARTIAL_AT_DEADLINE, CLType::Bool),
+        Parameter::new(ARG_GRACE_REFUND, CLType::Bool),
+        Parameter::new(ARG_MIN_STAKE_DURATION, CLType::U64),
+        Parameter::new(ARG_ORACLE, CLType::ByteArray(32)),
+        Parameter::new(ARG_CONDITION_KEY, CLType::String),
+        Parameter::new(ARG_KEEP_EXCESS, CLType::Bool),
+        Parameter::new(ARG_ENCRYPTED_MEMO, CLType::List(Box::new(CLType::U8))),
+        Parameter::new(ARG_GAS_REBATE, CLType::U64),
+        Parameter::new(ARG_SETTLEMENT_PURSE, CLType::URef),
+        Parameter::new(ARG_TAGS, CLType::List(Box::new(CLType::String))),
+        Parameter::new(ARG_MAX_ACCUMULATED, CLType::U64),
+        Parameter::new(ARG_CHARITY, CLType::Key),
+        Parameter::new(ARG_CHARITY_BPS, CLType::U64),
+        Parameter::new(ARG_PURSE, CLType::URef),
+    ]
+}
+
+fn get_main_purse() -> casper_types::URef {
+    let key = runtime::get_key(CONTRACT_PURSE_KEY).unwrap_or_revert();
+    key.into_uref().unwrap_or_revert()
+}
+
+fn get_escrow_purse(escrow_id: u64) -> casper_types::URef {
+    let key = runtime::get_key(&escrow_purse_key(escrow_id)).unwrap_or_revert();
+    key.into_uref().unwrap_or_revert()
+}
+
+/// Stores a new escrow owned by the caller, takes the creation deposit and
+/// returns the creation message.
+fn open_escrow(
+    escrow_id: u64,
+    amount: u64,
+    target_amount: u64,
+    deadline: u64,
+    exclude_creator: bool,
+    memo: String,
+    cosigner: Option<AccountHash>,
+) -> String {
+    let creator = runtime::get_caller();
+    record_creation(creator);
+    let message = init_escrow(creator, escrow_id, amount, target_amount, deadline, exclude_creator, memo, cosigner);
+    take_creation_deposit(escrow_id);
+    message
+}
+
+/// Counts an escrow created by `creator` against the creation rate limit,
+/// reverting once the current window's allowance is used up.
+fn record_creation(creator: AccountHash) {
+    let creation_rate_limit: u64 = read_named_value_or(CREATION_RATE_LIMIT_KEY, 0u64);
+    if creation_rate_limit == 0 {
+        return;
+    }
+
+    let (window_start, created) = creation_window(creator);
+    if created >= creation_rate_limit {
+        runtime::revert(casper_types::ApiError::User(ERR_RATE_LIMITED));
+    }
+    write_named_value(&creator_creation_window_key(creator), (window_start, created + 1));
+}
+
+/// The creator's rate limit window as `(window_start, created)`, starting a
+/// fresh one at the current block time if the last has run out.
+fn creation_window(creator: AccountHash) -> (u64, u64) {
+    let creation_rate_window: u64 = read_named_value_or(CREATION_RATE_WINDOW_KEY, DEFAULT_CREATION_RATE_WINDOW);
+    let now = now_millis();
+    let window_key = creator_creation_window_key(creator);
+    if runtime::has_key(&window_key) {
+        let (window_start, created): (u64, u64) = read_named_value(&window_key);
+        if now < window_start.saturating_add(creation_rate_window) {
+            return (window_start, created);
+        }
+    }
+    (now, 0)
+}
+
+/// Holds the contract's creation deposit, if any, from the `purse` argument.
+fn take_creation_deposit(escrow_id: u64) {
+    let creation_deposit: u64 = read_named_value_or(CREATION_DEPOSIT_KEY, 0u64);
+    if creation_deposit > 0 {
+        let source_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+        let deposit_purse = system::create_purse();
+        system::transfer_from_purse_to_purse(
+            source_purse,
+            deposit_purse,
+            U512::from(creation_deposit),
+            None
+        ).unwrap_or_revert();
+        runtime::put_key(&escrow_deposit_purse_key(escrow_id), deposit_purse.into());
+    }
+    let deposit_key = escrow_deposit_key(escrow_id);
+    let deposit_uref = storage::new_uref(creation_deposit);
+    runtime::put_key(&deposit_key, deposit_uref.into());
+}
+
+/// Stores a new escrow owned by `creator` and returns the creation message.
+#[allow(clippy::too_many_arguments)]
+fn init_escrow(
+    creator: AccountHash,
+    escrow_id: u64,
+    amount: u64,
+    target_amount: u64,
+    deadline: u64,
+    exclude_creator: bool,
+    memo: String,
+    cosigner: Option<AccountHash>,
+) -> String {
+    require_not_frozen();
+
+    // Reusing a live id would hand its funds and creator rights to the caller.
+    if runtime::has_key(&escrow_key(escrow_id)) {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_EXISTS));
+    }
+
+    // A zero share or target would let the escrow fill without anyone paying.
+    if amount == 0 || target_amount == 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_ZERO_AMOUNT));
+    }
+    require_memo_length(&memo);
+
+    let creator_escrows_key = creator_escrows_key(creator);
+    let mut creator_escrows: Vec<u64> = read_named_value_or(&creator_escrows_key, Vec::new());
+    let max_escrows_per_creator: u64 = read_named_value_or(MAX_ESCROWS_PER_CREATOR_KEY, DEFAULT_MAX_ESCROWS_PER_CREATOR);
+    if creator_escrows.len() as u64 >= max_escrows_per_creator {
+        runtime::revert(casper_types::ApiError::User(ERR_TOO_MANY_ESCROWS));
+    }
+    creator_escrows.push(escrow_id);
+    write_named_value(&creator_escrows_key, creator_escrows);
+
+    let key = escrow_key(escrow_id);
+    let amount_uref = storage::new_uref(amount);
+    runtime::put_key(&key, amount_uref.into());
+
+    let target_key = escrow_target_key(escrow_id);
+    let target_uref = storage::new_uref(target_amount);
+    runtime::put_key(&target_key, target_uref.into());
+
+    let deadline_key = escrow_deadline_key(escrow_id);
+    let deadline_uref = storage::new_uref(deadline);
+    runtime::put_key(&deadline_key, deadline_uref.into());
+
+    let creator_key = escrow_creator_key(escrow_id);
+    let creator_uref = storage::new_uref(creator);
+    runtime::put_key(&creator_key, creator_uref.into());
+
+    let exclude_creator_key = escrow_exclude_creator_key(escrow_id);
+    let exclude_creator_uref = storage::new_uref(exclude_creator);
+    runtime::put_key(&exclude_creator_key, exclude_creator_uref.into());
+
+    let memo_key = escrow_memo_key(escrow_id);
+    let memo_uref = storage::new_uref(memo);
+    runtime::put_key(&memo_key, memo_uref.into());
+
+    let created_at_key = escrow_created_at_key(escrow_id);
+    let created_at_uref = storage::new_uref(now_millis());
+    runtime::put_key(&created_at_key, created_at_uref.into());
+
+    if let Some(cosigner) = cosigner {
+        let cosigner_key = escrow_cosigner_key(escrow_id);
+        let cosigner_uref = storage::new_uref(cosigner);
+        runtime::put_key(&cosigner_key, cosigner_uref.into());
+
+        write_named_value(&escrow_creator_approved_key(escrow_id), false);
+        write_named_value(&escrow_cosigner_approved_key(escrow_id), false);
+    }
+
+    let joined_key = escrow_joined_key(escrow_id);
+    let joined_uref = storage::new_uref(false);
+    runtime::put_key(&joined_key, joined_uref.into());
+
+    let participants_key = escrow_participants_key(escrow_id);
+    let participants: Vec<AccountHash> = vec![];
+    let participants_uref = storage::new_uref(participants);
+    runtime::put_key(&participants_key, participants_uref.into());
+
+    let total_staked_key = escrow_total_staked_key(escrow_id);
+    let total_staked_uref = storage::new_uref(0u64);
+    runtime::put_key(&total_staked_key, total_staked_uref.into());
+
+    let joined_count_key = escrow_joined_count_key(escrow_id);
+    let joined_count_uref = storage::new_uref(0u64);
+    runtime::put_key(&joined_count_key, joined_count_uref.into());
+
+    let total_yield_key = escrow_total_yield_key(escrow_id);
+    let total_yield_uref = storage::new_uref(0u64);
+    runtime::put_key(&total_yield_key, total_yield_uref.into());
+
+    let completed_key = escrow_completed_key(escrow_id);
+    let completed_uref = storage::new_uref(false);
+    runtime::put_key(&completed_key, completed_uref.into());
+
+    set_status(escrow_id, STATUS_OPEN);
+
+    let settle_after_key = escrow_settle_after_key(escrow_id);
+    let settle_after_uref = storage::new_uref(0u64);
+    runtime::put_key(&settle_after_key, settle_after_uref.into());
+
+    let escrow_purse = system::create_purse();
+    let escrow_purse_key = escrow_purse_key(escrow_id);
+    runtime::put_key(&escrow_purse_key, escrow_purse.into());
+
+    let yield_purse = system::create_purse();
+    let yield_purse_key = escrow_yield_purse_key(escrow_id);
+    runtime::put_key(&yield_purse_key, yield_purse.into());
+
+    emit_event("escrow_created", escrow_id, &format!("amount:{},target:{},deadline:{}", amount, target_amount, deadline));
+
+    format!("Escrow {} created with target {}", escrow_id, target_amount)
+}
+
+// ================= ENTRY POINTS =================
+
+#[no_mangle]
+pub extern "C" fn create_escrow() {
+    let message = create_escrow_from_args();
+
+    runtime::ret(
+        CLValue::from_t(message)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Creates an escrow like `create_escrow` that, when it completes, reopens
+/// with the same settings under the next free escrow id. Participants join
+/// each period's escrow afresh.
+#[no_mangle]
+pub extern "C" fn create_recurring_escrow() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let message = create_escrow_from_args();
+    write_named_value(&escrow_recurring_key(escrow_id), true);
+
+    runtime::ret(
+        CLValue::from_t(message)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Opens an escrow from `create_escrow`'s arguments and returns the creation message.
+fn create_escrow_from_args() -> String {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
+    let target_amount: u64 = runtime::get_named_arg(ARG_TARGET_AMOUNT);
+    // An explicit `deadline` of 0 still creates an escrow with no deadline.
+    let deadline: u64 = runtime::try_get_named_arg(ARG_DEADLINE).unwrap_or_else(|| {
+        let default_deadline_offset: u64 = read_named_value_or(DEFAULT_DEADLINE_OFFSET_KEY, 0u64);
+        if default_deadline_offset > 0 {
+            now_millis().saturating_add(default_deadline_offset)
+        } else {
+            0
+        }
+    });
+    let exclude_creator: bool = runtime::try_get_named_arg(ARG_EXCLUDE_CREATOR).unwrap_or(false);
+    let memo: String = runtime::try_get_named_arg(ARG_MEMO).unwrap_or_default();
+    let cosigner: Option<AccountHash> = runtime::try_get_named_arg(ARG_COSIGNER);
+    let escrow_admin: Option<AccountHash> = runtime::try_get_named_arg(ARG_ESCROW_ADMIN);
+    let gating_nft: Option<ContractHash> = runtime::try_get_named_arg(ARG_GATING_NFT);
+    let settle_partial: bool = runtime::try_get_named_arg(ARG_SETTLE_PARTIAL_AT_DEADLINE).unwrap_or(false);
+    // Partial settlement always pays the creator.
+    let settle_to_creator: bool = settle_partial || runtime::try_get_named_arg(ARG_SETTLE_TO_CREATOR).unwrap_or(false);
+    let grace_refund: bool = runtime::try_get_named_arg(ARG_GRACE_REFUND).unwrap_or(false);
+    let min_stake_duration: u64 = runtime::try_get_named_arg(ARG_MIN_STAKE_DURATION).unwrap_or(0);
+    let oracle: Option<ContractHash> = runtime::try_get_named_arg(ARG_ORACLE);
+    let keep_excess: bool = runtime::try_get_named_arg(ARG_KEEP_EXCESS).unwrap_or(false);
+    let encrypted_memo: Option<Bytes> = runtime::try_get_named_arg(ARG_ENCRYPTED_MEMO);
+    if encrypted_memo.as_ref().is_some_and(|memo| memo.len() > MAX_ENCRYPTED_MEMO_LENGTH) {
+        runtime::revert(casper_types::ApiError::User(ERR_ENCRYPTED_MEMO_TOO_LONG));
+    }
+    let gas_rebate: u64 = runtime::try_get_named_arg(ARG_GAS_REBATE).unwrap_or(0);
+    if gas_rebate > amount {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_GAS_REBATE));
+    }
+    let settlement_purse: Option<casper_types::URef> = runtime::try_get_named_arg(ARG_SETTLEMENT_PURSE);
+    if settlement_purse.is_some_and(|purse| !purse.is_addable()) {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_SETTLEMENT_PURSE));
+    }
+    let tags: Vec<String> = runtime::try_get_named_arg(ARG_TAGS).unwrap_or_default();
+    require_valid_tags(&tags);
+    let charity: Option<(AccountHash, u64)> = runtime::try_get_named_arg::<AccountHash>(ARG_CHARITY)
+        .map(|charity| (charity, runtime::get_named_arg(ARG_CHARITY_BPS)));
+    // Only creator payouts donate; withdrawal escrows would never pay it.
+    if charity.is_some_and(|(charity, charity_bps)| {
+        !settle_to_creator || charity == AccountHash::default() || charity_bps > BPS_DENOMINATOR
+    }) {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_CHARITY));
+    }
+
+    let message = open_escrow(escrow_id, amount, target_amount, deadline, exclude_creator, memo, cosigner);
+
+    if let Some(gating_nft) = gating_nft {
+        let gating_nft_key = escrow_gating_nft_key(escrow_id);
+        let gating_nft_uref = storage::new_uref(gating_nft);
+        runtime::put_key(&gating_nft_key, gating_nft_uref.into());
+    }
+
+    let settle_to_creator_key = escrow_settle_to_creator_key(escrow_id);
+    let settle_to_creator_uref = storage::new_uref(settle_to_creator);
+    runtime::put_key(&settle_to_creator_key, settle_to_creator_uref.into());
+
+    let settle_partial_key = escrow_settle_partial_key(escrow_id);
+    let settle_partial_uref = storage::new_uref(settle_partial);
+    runtime::put_key(&settle_partial_key, settle_partial_uref.into());
+
+    let grace_refund_key = escrow_grace_refund_key(escrow_id);
+    let grace_refund_uref = storage::new_uref(grace_refund);
+    runtime::put_key(&grace_refund_key, grace_refund_uref.into());
+
+    let min_stake_duration_key = escrow_min_stake_duration_key(escrow_id);
+    let min_stake_duration_uref = storage::new_uref(min_stake_duration);
+    runtime::put_key(&min_stake_duration_key, min_stake_duration_uref.into());
+
+    let keep_excess_key = escrow_keep_excess_key(escrow_id);
+    let keep_excess_uref = storage::new_uref(keep_excess);
+    runtime::put_key(&keep_excess_key, keep_excess_uref.into());
+
+    let gas_rebate_key = escrow_gas_rebate_key(escrow_id);
+    let gas_rebate_uref = storage::new_uref(gas_rebate);
+    runtime::put_key(&gas_rebate_key, gas_rebate_uref.into());
+
+    // Kept as the named key itself so the contract retains add access to it.
+    if let Some(escrow_admin) = escrow_admin {
+        let escrow_admin_key = escrow_admin_key(escrow_id);
+        let escrow_admin_uref = storage::new_uref(escrow_admin);
+        runtime::put_key(&escrow_admin_key, escrow_admin_uref.into());
+    }
+
+    if let Some(settlement_purse) = settlement_purse {
+        runtime::put_key(&escrow_settlement_purse_key(escrow_id), settlement_purse.into());
+    }
+
+    if let Some(encrypted_memo) = encrypted_memo {
+        let encrypted_memo_key = escrow_encrypted_memo_key(escrow_id);
+        let encrypted_memo_uref = storage::new_uref(encrypted_memo);
+        runtime::put_key(&encrypted_memo_key, encrypted_memo_uref.into());
+    }
+
+    if let Some(oracle) = oracle {
+        let condition_key: String = runtime::get_named_arg(ARG_CONDITION_KEY);
+
+        let oracle_key = escrow_oracle_key(escrow_id);
+        let oracle_uref = storage::new_uref(oracle);
+        runtime::put_key(&oracle_key, oracle_uref.into());
+
+        let condition_key_key = escrow_condition_key(escrow_id);
+        let condition_key_uref = storage::new_uref(condition_key);
+        runtime::put_key(&condition_key_key, condition_key_uref.into());
+    }
+
+    if let Some(charity) = charity {
+        let charity_key = escrow_charity_key(escrow_id);
+        let charity_uref = storage::new_uref(charity);
+        runtime::put_key(&charity_key, charity_uref.into());
+    }
+
+    index_tags(escrow_id, tags);
+    store_accumulated_cap(escrow_id);
+
+    message
+}
+
+/// Creates an open pot: anyone may `contribute` any amount of at least
+/// `min_contribution` until `target_amount` is pooled, after which it settles
+/// to the creator like any funded escrow.
+#[no_mangle]
+pub extern "C" fn create_open_pot() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let target_amount: u64 = runtime::get_named_arg(ARG_TARGET_AMOUNT);
+    let min_contribution: u64 = runtime::get_named_arg(ARG_MIN_CONTRIBUTION);
+
+    if min_contribution > target_amount {
+        runtime::revert(casper_types::ApiError::User(ERR_CONTRIBUTION_TOO_SMALL));
+    }
+
+    let message = open_escrow(escrow_id, min_contribution, target_amount, 0, false, String::new(), None);
+    write_named_value(&escrow_open_pot_key(escrow_id), true);
+    write_named_value(&escrow_settle_to_creator_key(escrow_id), true);
+    store_accumulated_cap(escrow_id);
+
+    runtime::ret(
+        CLValue::from_t(message)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Creates an escrow whose pool is paid to `recipients` on settlement instead
+/// of being withdrawn by participants. Recipient amounts must sum to the target.
+#[no_mangle]
+pub extern "C" fn create_escrow_multi_recipient() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
+    let target_amount: u64 = runtime::get_named_arg(ARG_TARGET_AMOUNT);
+    let recipients: Vec<(AccountHash, u64)> = runtime::get_named_arg(ARG_RECIPIENTS);
+
+    if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_RECIPIENTS));
+    }
+    if recipients.iter().any(|(_, recipient_amount)| *recipient_amount == 0) {
+        runtime::revert(casper_types::ApiError::User(ERR_SPLIT_TOO_SMALL));
+    }
+    let scheduled = recipients
+        .iter()
+        .try_fold(0u64, |sum, (_, recipient_amount)| sum.checked_add(*recipient_amount));
+    if scheduled != Some(target_amount) {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_RECIPIENTS));
+    }
+
+    let message = open_escrow(escrow_id, amount, target_amount, 0, false, String::new(), None);
+
+    let recipients_key = escrow_recipients_key(escrow_id);
+    let recipients_uref = storage::new_uref(recipients);
+    runtime::put_key(&recipients_key, recipients_uref.into());
+
+    runtime::ret(
+        CLValue::from_t(message)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Creates several independent escrows for the caller in one call. Each entry
+/// is `(escrow_id, amount, target_amount)` with no deadline or memo.
+#[no_mangle]
+pub extern "C" fn create_escrow_batch() {
+    let escrows: Vec<(u64, u64, u64)> = runtime::get_named_arg(ARG_ESCROWS);
+
+    if escrows.len() as u64 > MAX_ESCROW_BATCH_SIZE {
+        runtime::revert(casper_types::ApiError::User(ERR_BATCH_TOO_LARGE));
+    }
+
+    let messages: Vec<String> = escrows
+        .into_iter()
+        .map(|(escrow_id, amount, target_amount)| {
+            open_escrow(escrow_id, amount, target_amount, 0, false, String::new(), None)
+        })
+        .collect();
+
+    runtime::ret(
+        CLValue::from_t(messages)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn save_template() {
+    let template_name: String = runtime::get_named_arg(ARG_TEMPLATE_NAME);
+    let num_participants: u64 = runtime::get_named_arg(ARG_NUM_PARTICIPANTS);
+    let deadline_offset: u64 = runtime::try_get_named_arg(ARG_DEADLINE_OFFSET).unwrap_or(0);
+    let exclude_creator: bool = runtime::try_get_named_arg(ARG_EXCLUDE_CREATOR).unwrap_or(false);
+    let creator = runtime::get_caller();
+
+    if num_participants == 0 || template_name.is_empty() || template_name.len() > MAX_TEMPLATE_NAME_LENGTH {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_TEMPLATE));
+    }
+
+    write_named_value(&template_key(creator, &template_name), (num_participants, deadline_offset, exclude_creator));
+
+    emit_contract_event("template_saved", &format!("creator:{},name:{}", creator, template_name));
+}
+
+/// Creates an escrow from one of the caller's templates, splitting
+/// `target_amount` evenly across the template's participants. Any remainder
+/// falls to the last slot, as in `get_split_schedule`; targets whose rounded
+/// up split would leave fewer slots than participants are rejected.
+#[no_mangle]
+pub extern "C" fn create_from_template() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let template_name: String = runtime::get_named_arg(ARG_TEMPLATE_NAME);
+    let target_amount: u64 = runtime::get_named_arg(ARG_TARGET_AMOUNT);
+
+    let template_key = template_key(runtime::get_caller(), &template_name);
+    if !runtime::has_key(&template_key) {
+        runtime::revert(casper_types::ApiError::User(ERR_TEMPLATE_NOT_FOUND));
+    }
+    let (num_participants, deadline_offset, exclude_creator): (u64, u64, bool) = read_named_value(&template_key);
+
+    // Fewer motes than participants would leave some of them a zero share.
+    if target_amount / num_participants == 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_SPLIT_TOO_SMALL));
+    }
+    let amount = target_amount.div_ceil(num_participants);
+    // e.g. 9 across 4 rounds up to a split of 3, which only makes 3 slots.
+    if target_amount.div_ceil(amount) != num_participants {
+        runtime::revert(casper_types::ApiError::User(ERR_UNEVEN_TEMPLATE_SPLIT));
+    }
+    let deadline = if deadline_offset > 0 {
+        now_millis().saturating_add(deadline_offset)
+    } else {
+        0
+    };
+
+    let message = open_escrow(escrow_id, amount, target_amount, deadline, exclude_creator, String::new(), None);
+
+    runtime::ret(
+        CLValue::from_t(message)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Creates a fresh open escrow with the same configuration as an existing
+/// one, in any status. A source deadline carries over as the same offset
+/// from creation. Payments, approvals and votes are not copied.
+#[no_mangle]
+pub extern "C" fn clone_escrow() {
+    let source_id: u64 = runtime::get_named_arg(ARG_SOURCE_ESCROW_ID);
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    if !read_named_value_or(ANYONE_CAN_CLONE_KEY, false) {
+        require_creator(source_id);
+    }
+
+    let creator = runtime::get_caller();
+    record_creation(creator);
+    let message = copy_escrow(source_id, escrow_id, creator);
+    take_creation_deposit(escrow_id);
+
+    emit_event("escrow_cloned", escrow_id, &format!("source:{}", source_id));
+
+    runtime::ret(
+        CLValue::from_t(message)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Opens `escrow_id` for `creator` with `source_id`'s settings and returns the
+/// creation message. The deadline keeps the source's length from creation.
+fn copy_escrow(source_id: u64, escrow_id: u64, creator: AccountHash) -> String {
+    let amount: u64 = read_named_value(&escrow_key(source_id));
+    let target_amount: u64 = read_named_value(&escrow_target_key(source_id));
+    let source_deadline: u64 = read_named_value(&escrow_deadline_key(source_id));
+    let deadline = if source_deadline > 0 {
+        let source_created_at: u64 = read_named_value_or(&escrow_created_at_key(source_id), 0u64);
+        now_millis().saturating_add(source_deadline.saturating_sub(source_created_at))
+    } else {
+        0
+    };
+    let exclude_creator: bool = read_named_value_or(&escrow_exclude_creator_key(source_id), false);
+    let memo: String = read_named_value_or(&escrow_memo_key(source_id), String::new());
+    let cosigner_key = escrow_cosigner_key(source_id);
+    let cosigner: Option<AccountHash> = if runtime::has_key(&cosigner_key) {
+        Some(read_named_value(&cosigner_key))
+    } else {
+        None
+    };
+
+    let message = init_escrow(creator, escrow_id, amount, target_amount, deadline, exclude_creator, memo, cosigner);
+
+    copy_escrow_value::<ContractHash>(escrow_gating_nft_key, source_id, escrow_id);
+    copy_escrow_value::<bool>(escrow_settle_to_creator_key, source_id, escrow_id);
+    copy_escrow_value::<bool>(escrow_settle_partial_key, source_id, escrow_id);
+    copy_escrow_value::<bool>(escrow_grace_refund_key, source_id, escrow_id);
+    copy_escrow_value::<u64>(escrow_min_stake_duration_key, source_id, escrow_id);
+    copy_escrow_value::<bool>(escrow_keep_excess_key, source_id, escrow_id);
+    copy_escrow_value::<u64>(escrow_gas_rebate_key, source_id, escrow_id);
+    copy_escrow_value::<u64>(escrow_max_accumulated_key, source_id, escrow_id);
+    copy_escrow_value::<(AccountHash, u64)>(escrow_charity_key, source_id, escrow_id);
+    // The escrow admin and settlement purse belong to the source creator, so
+    // only their own clones keep them.
+    let source_creator: AccountHash = read_named_value(&escrow_creator_key(source_id));
+    if creator == source_creator {
+        copy_escrow_value::<AccountHash>(escrow_admin_key, source_id, escrow_id);
+        if let Some(settlement_purse) = runtime::get_key(&escrow_settlement_purse_key(source_id)) {
+            runtime::put_key(&escrow_settlement_purse_key(escrow_id), settlement_purse);
+        }
+    }
+    copy_escrow_value::<bool>(escrow_open_pot_key, source_id, escrow_id);
+    copy_escrow_value::<ContractHash>(escrow_oracle_key, source_id, escrow_id);
+    copy_escrow_value::<String>(escrow_condition_key, source_id, escrow_id);
+    copy_escrow_value::<Vec<(AccountHash, u64)>>(escrow_recipients_key, source_id, escrow_id);
+    copy_escrow_value::<Bytes>(escrow_encrypted_memo_key, source_id, escrow_id);
+    index_tags(escrow_id, read_named_value_or(&escrow_tags_key(source_id), Vec::new()));
+
+    message
+}
+
+#[no_mangle]
+pub extern "C" fn delete_template() {
+    let template_name: String = runtime::get_named_arg(ARG_TEMPLATE_NAME);
+    let creator = runtime::get_caller();
+
+    let template_key = template_key(creator, &template_name);
+    if !runtime::has_key(&template_key) {
+        runtime::revert(casper_types::ApiError::User(ERR_TEMPLATE_NOT_FOUND));
+    }
+    runtime::remove_key(&template_key);
+
+    emit_contract_event("template_deleted", &format!("creator:{},name:{}", creator, template_name));
+}
+
+#[no_mangle]
+pub extern "C" fn join_escrow() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let caller = runtime::get_caller();
+
+    require_can_join(escrow_id, caller);
+
+    if is_retried_call(escrow_id, caller) {
+        return;
+    }
+
+    let joined_key = escrow_joined_key(escrow_id);
+
+    match runtime::get_key(&joined_key) {
+        Some(key) => {
+            let uref = key.into_uref().unwrap_or_revert();
+            storage::write(uref, true);
+        }
+        None => {
+            let uref = storage::new_uref(true);
+            runtime::put_key(&joined_key, uref.into());
+        }
+    }
+
+    let participants_key = escrow_participants_key(escrow_id);
+    if let Some(key) = runtime::get_key(&participants_key) {
+        let participants_uref = key.into_uref().unwrap_or_revert();
+        let mut participants: Vec<AccountHash> = storage::read(participants_uref)
+            .unwrap_or_revert()
+            .unwrap_or_revert();
+
+        if !participants.contains(&caller) {
+            participants.push(caller);
+            storage::write(participants_uref, participants);
+            write_named_value(&escrow_last_join_time_key(escrow_id), now_millis());
+            add_to_account_escrows(caller, escrow_id);
+
+            let liquid_balance_key = escrow_liquid_balance_key(escrow_id, caller);
+            let liquid_balance_uref = storage::new_uref(0u64);
+            runtime::put_key(&liquid_balance_key, liquid_balance_uref.into());
+
+            emit_event("liquid_staking_enabled", escrow_id, &format!("participant:{}", caller));
+        }
+    }
+
+    emit_event("escrow_joined", escrow_id, &format!("participant:{}", caller));
+}
+
+/// Returns, for each of up to `MAX_PAGE_SIZE` escrow ids, whether `account`
+/// currently holds stake in it. Unknown escrows report false.
+#[no_mangle]
+pub extern "C" fn check_memberships() {
+    let escrow_ids: Vec<u64> = runtime::get_named_arg(ARG_ESCROW_IDS);
+    let account: AccountHash = runtime::get_named_arg(ARG_ACCOUNT);
+
+    if escrow_ids.len() as u64 > MAX_PAGE_SIZE {
+        runtime::revert(casper_types::ApiError::User(ERR_BATCH_TOO_LARGE));
+    }
+
+    let memberships: Vec<bool> = escrow_ids
+        .into_iter()
+        .map(|escrow_id| read_named_value_or(&escrow_stake_key(escrow_id, account), 0u64) > 0)
+        .collect();
+
+    runtime::ret(
+        CLValue::from_t(memberships)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns `(allowed, reason)` for `account` joining the escrow now, where
+/// `reason` is the error code `join_escrow` would revert with, or 0.
+#[no_mangle]
+pub extern "C" fn can_join() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let account: AccountHash = runtime::get_named_arg(ARG_ACCOUNT);
+
+    // CLType has no u16; every error code fits in a u8.
+    let result = match join_blocker(escrow_id, account) {
+        Some(reason) => (false, u8::try_from(reason).unwrap_or(u8::MAX)),
+        None => (true, 0u8),
+    };
+
+    runtime::ret(
+        CLValue::from_t(result)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn stake() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    let completed_key = escrow_completed_key(escrow_id);
+    let completed_storage_key = runtime::get_key(&completed_key).unwrap_or_revert();
+    let completed_uref = completed_storage_key.into_uref().unwrap_or_revert();
+    let is_completed: bool = storage::read(completed_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(103));
+    }
+    require_can_join(escrow_id, participant);
+
+    let source_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+    
+    let escrow_purse_key = escrow_purse_key(escrow_id);
+    let escrow_purse_storage_key = runtime::get_key(&escrow_purse_key).unwrap_or_revert();
+    let escrow_purse = escrow_purse_storage_key.into_uref().unwrap_or_revert();
+
+    system::transfer_from_purse_to_purse(
+        source_purse,
+        escrow_purse,
+        U512::from(amount),
+        None
+    ).unwrap_or_revert();
+
+    credit_stake(escrow_id, participant, amount);
+
+    emit_event("staked", escrow_id, &format!("participant:{},amount:{},liquid_issued:{}", participant, amount, amount));
+
+    runtime::ret(
+        CLValue::from_t(format!("Staked {} to escrow {} with liquid tokens", amount, escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Pays part of the caller's share. Installments accumulate until they reach
+/// the escrow's per-participant amount; only then is the caller credited as a
+/// staker and counted in `joined_count`. Contributions are capped at the
+/// split: an overshooting final installment only takes what is owed, and
+/// paying again once fully paid reverts.
+#[no_mangle]
+pub extern "C" fn pay_installment() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
+    let source_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+    let caller = runtime::get_caller();
+
+    if amount == 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_ZERO_AMOUNT));
+    }
+    require_can_join(escrow_id, caller);
+
+    let is_completed: bool = read_named_value(&escrow_completed_key(escrow_id));
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(103));
+    }
+
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let paid_key = escrow_paid_key(escrow_id, caller);
+    let previously_paid: u64 = read_named_value_or(&paid_key, 0u64);
+
+    if is_retried_call(escrow_id, caller) {
+        runtime::ret(
+            CLValue::from_t(format!("Paid {} of {} to escrow {}", previously_paid, split, escrow_id))
+                .unwrap_or_revert(),
+        );
+    }
+
+    if previously_paid >= split {
+        runtime::revert(casper_types::ApiError::User(ERR_ALREADY_FULLY_PAID));
+    }
+
+    // Only the part up to the split is principal. Any overshoot stays in
+    // `purse`, unless the escrow keeps it as yield for the pool.
+    let excess = amount.saturating_sub(split - previously_paid);
+    let amount = amount - excess;
+    let escrow_purse = get_escrow_purse(escrow_id);
+
+    if excess > 0 && read_named_value_or(&escrow_keep_excess_key(escrow_id), false) {
+        let yield_purse = runtime::get_key(&escrow_yield_purse_key(escrow_id))
+            .unwrap_or_revert()
+            .into_uref()
+            .unwrap_or_revert();
+        system::transfer_from_purse_to_purse(
+            source_purse,
+            yield_purse,
+            U512::from(excess),
+            None
+        ).unwrap_or_revert();
+
+        let total_yield_key = escrow_total_yield_key(escrow_id);
+        let total_yield: u64 = read_named_value(&total_yield_key);
+        write_named_value(&total_yield_key, total_yield + excess);
+
+        emit_event("excess_kept", escrow_id, &format!("participant:{},amount:{}", caller, excess));
+    }
+
+    system::transfer_from_purse_to_purse(
+        source_purse,
+        escrow_purse,
+        U512::from(amount),
+        None
+    ).unwrap_or_revert();
+
+    let paid = previously_paid + amount;
+    write_named_value(&paid_key, paid);
+    record_join_time(escrow_id, caller);
+
+    let payers_key = escrow_payers_key(escrow_id);
+    let mut payers: Vec<AccountHash> = read_named_value_or(&payers_key, Vec::new());
+    if !payers.contains(&caller) {
+        payers.push(caller);
+        write_named_value(&payers_key, payers);
+    }
+
+    emit_event("installment_paid", escrow_id, &format!("participant:{},amount:{},paid:{},split:{}", caller, amount, paid, split));
+
+    if paid == split {
+        credit_stake(escrow_id, caller, paid);
+
+        emit_event("escrow_joined", escrow_id, &format!("participant:{}", caller));
+    }
+
+    runtime::ret(
+        CLValue::from_t(format!("Paid {} of {} to escrow {}", paid, split, escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Pays the full split for each of up to `MAX_ESCROW_BATCH_SIZE`
+/// `beneficiaries` in one transfer from `purse`, recording each as a
+/// separately joined participant who owns their stake and refunds. `amount`
+/// must be exactly one split per beneficiary, and none of them may have paid
+/// into the escrow yet.
+#[no_mangle]
+pub extern "C" fn fund_slots() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let beneficiaries: Vec<AccountHash> = runtime::get_named_arg(ARG_BENEFICIARIES);
+    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
+    let source_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+    let caller = runtime::get_caller();
+
+    if beneficiaries.is_empty() {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_PARTICIPANT_COUNT));
+    }
+    if beneficiaries.len() as u64 > MAX_ESCROW_BATCH_SIZE {
+        runtime::revert(casper_types::ApiError::User(ERR_BATCH_TOO_LARGE));
+    }
+    let is_completed: bool = read_named_value(&escrow_completed_key(escrow_id));
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(103));
+    }
+    if beneficiaries.len() as u64 > remaining_slots(escrow_id) {
+        runtime::revert(casper_types::ApiError::User(ERR_NO_OPEN_SLOT));
+    }
+
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let expected = split
+        .checked_mul(beneficiaries.len() as u64)
+        .unwrap_or_revert_with(casper_types::ApiError::User(ERR_LEDGER_OVERFLOW));
+    if amount != expected {
+        runtime::revert(casper_types::ApiError::User(ERR_SPLIT_MISMATCH));
+    }
+
+    for (i, beneficiary) in beneficiaries.iter().enumerate() {
+        require_can_join(escrow_id, *beneficiary);
+        let already_joined = beneficiaries[..i].contains(beneficiary)
+            || read_named_value_or(&escrow_paid_key(escrow_id, *beneficiary), 0u64) > 0
+            || read_named_value_or(&escrow_stake_key(escrow_id, *beneficiary), 0u64) > 0;
+        if already_joined {
+            runtime::revert(casper_types::ApiError::User(ERR_ALREADY_JOINED));
+        }
+    }
+
+    system::transfer_from_purse_to_purse(
+        source_purse,
+        get_escrow_purse(escrow_id),
+        U512::from(amount),
+        None
+    ).unwrap_or_revert();
+
+    let payers_key = escrow_payers_key(escrow_id);
+    let mut payers: Vec<AccountHash> = read_named_value_or(&payers_key, Vec::new());
+    for beneficiary in &beneficiaries {
+        write_named_value(&escrow_paid_key(escrow_id, *beneficiary), split);
+        if !payers.contains(beneficiary) {
+            payers.push(*beneficiary);
+        }
+    }
+    write_named_value(&payers_key, payers);
+
+    for beneficiary in &beneficiaries {
+        credit_stake(escrow_id, *beneficiary, split);
+
+        emit_event("slot_funded", escrow_id, &format!("participant:{},payer:{},amount:{}", beneficiary, caller, split));
+        emit_event("escrow_joined", escrow_id, &format!("participant:{}", beneficiary));
+    }
+
+    runtime::ret(
+        CLValue::from_t(format!("Funded {} slots of escrow {}", beneficiaries.len(), escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Adds a contribution to an open pot. Amounts below the pot's minimum are
+/// rejected unless they cover everything still missing; anything beyond the
+/// target is left in `purse`.
+#[no_mangle]
+pub extern "C" fn contribute() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
+    let caller = runtime::get_caller();
+
+    if !read_named_value_or(&escrow_open_pot_key(escrow_id), false) {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_OPEN_POT));
+    }
+    require_can_join(escrow_id, caller);
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_OPEN {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_OPEN));
+    }
+
+    let min_contribution: u64 = read_named_value(&escrow_key(escrow_id));
+    let target_amount: u64 = read_named_value(&escrow_target_key(escrow_id));
+    let total_staked: u64 = read_named_value(&escrow_total_staked_key(escrow_id));
+    let missing = target_amount - total_staked;
+    if amount < min_contribution.min(missing) {
+        runtime::revert(casper_types::ApiError::User(ERR_CONTRIBUTION_TOO_SMALL));
+    }
+
+    let accepted = amount.min(missing);
+    let source_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+    system::transfer_from_purse_to_purse(
+        source_purse,
+        get_escrow_purse(escrow_id),
+        U512::from(accepted),
+        None
+    ).unwrap_or_revert();
+    credit_stake(escrow_id, caller, accepted);
+
+    emit_event("contributed", escrow_id, &format!("participant:{},amount:{},returned:{},pot:{}", caller, accepted, amount - accepted, total_staked + accepted));
+
+    runtime::ret(
+        CLValue::from_t(format!("Contributed {} to escrow {}", accepted, escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns how much an open pot (or any escrow) has pooled so far.
+#[no_mangle]
+pub extern "C" fn get_pot_balance() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let total_staked: u64 = read_named_value(&escrow_total_staked_key(escrow_id));
+
+    runtime::ret(
+        CLValue::from_t(total_staked)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns a page of `(contributor, total)` pairs for everyone currently
+/// holding stake in the escrow, in the order they first contributed. Repeat
+/// contributions are summed into one total.
+#[no_mangle]
+pub extern "C" fn get_contributions() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let offset: u64 = runtime::get_named_arg(ARG_OFFSET);
+    let limit: u64 = runtime::get_named_arg(ARG_LIMIT);
+
+    let participants: Vec<AccountHash> = read_named_value(&escrow_participants_key(escrow_id));
+    let page: Vec<(AccountHash, u64)> = participants
+        .into_iter()
+        .map(|participant| {
+            let stake: u64 = read_named_value_or(&escrow_stake_key(escrow_id, participant), 0u64);
+            (participant, stake)
+        })
+        .filter(|(_, stake)| *stake > 0)
+        .skip(offset as usize)
+        .take(limit.min(MAX_PAGE_SIZE) as usize)
+        .collect();
+
+    runtime::ret(
+        CLValue::from_t(page)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn refund() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    // Leaving charges a fee and may pay the caller's purse, so participants
+    // can only leave on their own behalf.
+    if participant != runtime::get_caller() {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_PARTICIPANT));
+    }
+
+    let completed_key = escrow_completed_key(escrow_id);
+    let completed_storage_key = runtime::get_key(&completed_key).unwrap_or_revert();
+    let completed_uref = completed_storage_key.into_uref().unwrap_or_revert();
+    let is_completed: bool = storage::read(completed_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(100));
+    }
+
+    let stake_key = escrow_stake_key(escrow_id, participant);
+    let staked_amount: u64 = read_named_value_or(&stake_key, 0u64);
+
+    // Installments below the split are held in the escrow purse but not yet
+    // credited as stake, so they are refunded on top of the staked amount.
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let paid_key = escrow_paid_key(escrow_id, participant);
+    let paid: u64 = read_named_value_or(&paid_key, 0u64);
+    let fully_paid = paid > 0 && paid >= split;
+    let partial_amount = if fully_paid { 0u64 } else { paid };
+    let refund_amount = staked_amount + partial_amount;
+
+    if refund_amount == 0 {
+        runtime::revert(casper_types::ApiError::User(104));
+    }
+
+    let escrow_purse_key = escrow_purse_key(escrow_id);
+    let escrow_purse_storage_key = runtime::get_key(&escrow_purse_key).unwrap_or_revert();
+    let escrow_purse = escrow_purse_storage_key.into_uref().unwrap_or_revert();
+
+    let fee = leave_fee(escrow_id, participant, refund_amount);
+    if fee > refund_amount {
+        runtime::revert(casper_types::ApiError::User(ERR_FEE_EXCEEDS_REFUND));
+    }
+    let payout = refund_amount - fee;
+
+    if runtime::has_key(&escrow_refund_address_key(escrow_id, participant)) {
+        system::transfer_from_purse_to_account(
+            escrow_purse,
+            refund_address(escrow_id, participant),
+            U512::from(payout),
+            None
+        ).unwrap_or_revert();
+    } else {
+        let target_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+
+        system::transfer_from_purse_to_purse(
+            escrow_purse,
+            target_purse,
+            U512::from(payout),
+            None
+        ).unwrap_or_revert();
+    }
+
+    if fee > 0 {
+        let fee_receiver: AccountHash = read_named_value(FEE_RECEIVER_KEY);
+        system::transfer_from_purse_to_account(
+            escrow_purse,
+            fee_receiver,
+            U512::from(fee),
+            None
+        ).unwrap_or_revert();
+    }
+
+    write_named_value(&stake_key, 0u64);
+    runtime::remove_key(&escrow_join_time_key(escrow_id, participant));
+
+    if paid > 0 {
+        write_named_value(&paid_key, 0u64);
+    }
+    if staked_amount >= split {
+        let joined_count_key = escrow_joined_count_key(escrow_id);
+        let joined_count: u64 = read_named_value_or(&joined_count_key, 0u64);
+        write_named_value(&joined_count_key, joined_count.saturating_sub(1));
+        invite_next_waitlisted(escrow_id);
+    }
+
+    let liquid_balance_key = escrow_liquid_balance_key(escrow_id, participant);
+    if let Some(key) = runtime::get_key(&liquid_balance_key) {
+        let uref = key.into_uref().unwrap_or_revert();
+        let current_liquid: u64 = storage::read(uref)
+            .unwrap_or_revert()
+            .unwrap_or_revert();
+        if current_liquid >= staked_amount {
+            storage::write(uref, current_liquid - staked_amount);
+        } else {
+            storage::write(uref, 0u64);
+        }
+    }
+
+    let total_staked_key = escrow_total_staked_key(escrow_id);
+    let total_staked_storage_key = runtime::get_key(&total_staked_key).unwrap_or_revert();
+    let total_staked_uref = total_staked_storage_key.into_uref().unwrap_or_revert();
+    let current_total: u64 = storage::read(total_staked_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+    storage::write(total_staked_uref, current_total - staked_amount);
+
+    // A refund that drops the escrow back under target cancels a pending settlement.
+    let target_amount: u64 = read_named_value(&escrow_target_key(escrow_id));
+    let status_key = escrow_status_key(escrow_id);
+    let status: u8 = read_named_value_or(&status_key, STATUS_OPEN);
+    if status == STATUS_READY_TO_SETTLE && current_total - staked_amount < target_amount {
+        set_status(escrow_id, STATUS_OPEN);
+        write_named_value(&escrow_settle_after_key(escrow_id), 0u64);
+    }
+
+    let deadline: u64 = read_named_value_or(&escrow_deadline_key(escrow_id), 0u64);
+    if deadline > 0 && now_millis() >= deadline {
+        write_named_value(&escrow_expiry_refunded_key(escrow_id), true);
+    }
+
+    let total_refunded = add_to_ledger_total(TOTAL_REFUNDED_KEY, payout);
+
+    emit_event("refunded", escrow_id, &format!("participant:{},amount:{},fee:{},total_refunded:{}", participant, payout, fee, total_refunded));
+
+    runtime::ret(
+        CLValue::from_t(format!("Refunded {} from escrow {}", payout, escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Moves the caller's full stake from `source_escrow_id` to `escrow_id`
+/// without paying it out: they leave the source as with `refund`, but the
+/// funds go straight into the target's purse and fill a slot there. The
+/// stake must equal the target's split and the caller must not have paid
+/// into the target yet. No leave fee is charged: the stake stays in this
+/// contract, and leaving the target later pays the fee there. The caller's
+/// original join time carries over so that fee cannot be reset by moving.
+/// Disputed sources are locked until the dispute is resolved.
+#[no_mangle]
+pub extern "C" fn transfer_stake() {
+    let source_id: u64 = runtime::get_named_arg(ARG_SOURCE_ESCROW_ID);
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let caller = runtime::get_caller();
+
+    let source_completed: bool = read_named_value(&escrow_completed_key(source_id));
+    if source_completed {
+        runtime::revert(casper_types::ApiError::User(100));
+    }
+    require_not_refunding(source_id);
+    let source_status: u8 = read_named_value_or(&escrow_status_key(source_id), STATUS_OPEN);
+    if source_status == STATUS_DISPUTED {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_DISPUTED));
+    }
+    let source_split: u64 = read_named_value(&escrow_key(source_id));
+    let source_paid_key = escrow_paid_key(source_id, caller);
+    let source_paid: u64 = read_named_value_or(&source_paid_key, 0u64);
+    let source_stake_key = escrow_stake_key(source_id, caller);
+    let stake: u64 = read_named_value_or(&source_stake_key, 0u64);
+    if stake == 0 || source_paid < source_split {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_PARTICIPANT));
+    }
+
+    require_can_join(escrow_id, caller);
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_OPEN {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_OPEN));
+    }
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let paid_key = escrow_paid_key(escrow_id, caller);
+    let paid: u64 = read_named_value_or(&paid_key, 0u64);
+    if stake != split || paid > 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_SPLIT_MISMATCH));
+    }
+    if remaining_slots(escrow_id) == 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_NO_OPEN_SLOT));
+    }
+
+    system::transfer_from_purse_to_purse(
+        get_escrow_purse(source_id),
+        get_escrow_purse(escrow_id),
+        U512::from(stake),
+        None
+    ).unwrap_or_revert();
+
+    write_named_value(&source_stake_key, 0u64);
+    write_named_value(&source_paid_key, 0u64);
+    let source_join_time_key = escrow_join_time_key(source_id, caller);
+    let source_created_at: u64 = read_named_value_or(&escrow_created_at_key(source_id), 0u64);
+    let join_time: u64 = read_named_value_or(&source_join_time_key, source_created_at);
+    runtime::remove_key(&source_join_time_key);
+    let source_joined_count_key = escrow_joined_count_key(source_id);
+    let source_joined_count: u64 = read_named_value_or(&source_joined_count_key, 0u64);
+    write_named_value(&source_joined_count_key, source_joined_count.saturating_sub(1));
+    let source_liquid_key = escrow_liquid_balance_key(source_id, caller);
+    let source_liquid: u64 = read_named_value_or(&source_liquid_key, 0u64);
+    write_named_value(&source_liquid_key, source_liquid.saturating_sub(stake));
+    let source_total_key = escrow_total_staked_key(source_id);
+    let source_total: u64 = read_named_value(&source_total_key);
+    write_named_value(&source_total_key, source_total - stake);
+
+    // Leaving can drop the source back under target, like a refund would.
+    let source_target: u64 = read_named_value(&escrow_target_key(source_id));
+    if source_status == STATUS_READY_TO_SETTLE && source_total - stake < source_target {
+        set_status(source_id, STATUS_OPEN);
+        write_named_value(&escrow_settle_after_key(source_id), 0u64);
+    }
+    invite_next_waitlisted(source_id);
+
+    emit_event("stake_transferred_out", source_id, &format!("participant:{},amount:{},to:{}", caller, stake, escrow_id));
+
+    write_named_value(&paid_key, stake);
+    let payers_key = escrow_payers_key(escrow_id);
+    let mut payers: Vec<AccountHash> = read_named_value_or(&payers_key, Vec::new());
+    if !payers.contains(&caller) {
+        payers.push(caller);
+        write_named_value(&payers_key, payers);
+    }
+    write_named_value(&escrow_join_time_key(escrow_id, caller), join_time);
+    credit_stake(escrow_id, caller, stake);
+
+    emit_event("stake_transferred_in", escrow_id, &format!("participant:{},amount:{},from:{}", caller, stake, source_id));
+    emit_event("escrow_joined", escrow_id, &format!("participant:{}", caller));
+
+    runtime::ret(
+        CLValue::from_t(format!("Moved {} from escrow {} to escrow {}", stake, source_id, escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn deposit_yield() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
+
+    // Shares and dust are fixed when the escrow completes.
+    let is_completed: bool = read_named_value(&escrow_completed_key(escrow_id));
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(100));
+    }
+
+    let source_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+
+    let yield_purse_key = escrow_yield_purse_key(escrow_id);
+    let yield_purse_storage_key = runtime::get_key(&yield_purse_key).unwrap_or_revert();
+    let yield_purse = yield_purse_storage_key.into_uref().unwrap_or_revert();
+
+    system::transfer_from_purse_to_purse(
+        source_purse,
+        yield_purse,
+        U512::from(amount),
+        None
+    ).unwrap_or_revert();
+
+    let total_yield_key = escrow_total_yield_key(escrow_id);
+    let total_yield_storage_key = runtime::get_key(&total_yield_key).unwrap_or_revert();
+    let total_yield_uref = total_yield_storage_key.into_uref().unwrap_or_revert();
+    let current_yield: u64 = storage::read(total_yield_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+    storage::write(total_yield_uref, current_yield + amount);
+
+    emit_event("yield_deposited", escrow_id, &format!("amount:{}", amount));
+
+    runtime::ret(
+        CLValue::from_t(format!("Deposited {} yield to escrow {}", amount, escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn complete_escrow() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    require_settlement_unlocked(escrow_id);
+    let message = settle_escrow(escrow_id);
+
+    runtime::ret(
+        CLValue::from_t(message)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Settles each of up to `MAX_ESCROW_BATCH_SIZE` escrows that is funded and
+/// past every settlement check, as `complete_escrow` would, skipping the rest,
+/// including insolvent escrows and every escrow while the contract is frozen.
+/// Returns whether each escrow settled, in input order.
+#[no_mangle]
+pub extern "C" fn settle_batch() {
+    let escrow_ids: Vec<u64> = runtime::get_named_arg(ARG_ESCROW_IDS);
+
+    if escrow_ids.len() as u64 > MAX_ESCROW_BATCH_SIZE {
+        runtime::revert(casper_types::ApiError::User(ERR_BATCH_TOO_LARGE));
+    }
+
+    let frozen: bool = read_named_value_or(FROZEN_KEY, false);
+    let settled: Vec<bool> = escrow_ids
+        .into_iter()
+        .map(|escrow_id| {
+            let settleable = !frozen
+                && runtime::has_key(&escrow_key(escrow_id))
+                && read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN) == STATUS_READY_TO_SETTLE
+                && settlement_blocker(escrow_id).is_none()
+                && {
+                    let (total_staked, total_yield) = settlement_totals(escrow_id);
+                    is_solvent(escrow_id, total_staked, total_yield)
+                };
+            if settleable {
+                settle_escrow(escrow_id);
+            }
+            settleable
+        })
+        .collect();
+
+    runtime::ret(
+        CLValue::from_t(settled)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Completes an escrow created with `settle_partial_at_deadline` once its
+/// deadline has passed, paying the creator whatever fully-paid participants
+/// pooled even if the target was not reached. Installments short of the
+/// split are refunded first. Callable by anyone.
+#[no_mangle]
+pub extern "C" fn settle_at_deadline() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    if !read_named_value_or(&escrow_settle_partial_key(escrow_id), false) {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_PARTIAL_SETTLEMENT));
+    }
+    let is_completed: bool = read_named_value(&escrow_completed_key(escrow_id));
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(100));
+    }
+    let deadline: u64 = read_named_value(&escrow_deadline_key(escrow_id));
+    if deadline == 0 || now_millis() < deadline {
+        runtime::revert(casper_types::ApiError::User(ERR_DEADLINE_NOT_REACHED));
+    }
+    require_settlement_unlocked(escrow_id);
+
+    let (total_staked, _) = settlement_totals(escrow_id);
+    if total_staked == 0 {
+        runtime::revert(casper_types::ApiError::User(105));
+    }
+
+    let partial_payers: Vec<AccountHash> = principal_holders(escrow_id)
+        .into_iter()
+        .filter(|payer| read_named_value_or::<u64>(&escrow_stake_key(escrow_id, *payer), 0u64) == 0)
+        .collect();
+    let partial_refund = refund_principal(escrow_id, &partial_payers);
+    if partial_refund > 0 {
+        let total_refunded = add_to_ledger_total(TOTAL_REFUNDED_KEY, partial_refund);
+
+        emit_event("partial_installments_refunded", escrow_id, &format!("amount:{},total_refunded:{}", partial_refund, total_refunded));
+    }
+
+    let target_amount: u64 = read_named_value(&escrow_target_key(escrow_id));
+    emit_event("settled_at_deadline", escrow_id, &format!("total_staked:{},target:{}", total_staked, target_amount));
+
+    let message = complete_settlement(escrow_id);
+
+    runtime::ret(
+        CLValue::from_t(message)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns `(gross, fee, net_to_creator)` for settling a `ReadyToSettle`
+/// escrow now, without moving funds. Settlement charges no fee, so `fee` is
+/// 0; `net_to_creator` is 0 unless the escrow settles to its creator, and
+/// excludes gas rebates and any charity share.
+#[no_mangle]
+pub extern "C" fn preview_settlement() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_READY_TO_SETTLE {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_READY_TO_SETTLE));
+    }
+
+    let (total_staked, total_yield) = settlement_totals(escrow_id);
+    let gross = total_staked + total_yield;
+    let fee = 0u64;
+    let settles_to_creator = !runtime::has_key(&escrow_recipients_key(escrow_id))
+        && read_named_value_or(&escrow_settle_to_creator_key(escrow_id), false);
+    let net_to_creator = if settles_to_creator {
+        let (principal_to_creator, yield_to_creator) = creator_net(escrow_id, total_staked, total_yield);
+        principal_to_creator + yield_to_creator - fee
+    } else {
+        0
+    };
+
+    runtime::ret(
+        CLValue::from_t((gross, fee, net_to_creator))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Completes a `ReadyToSettle` escrow once its settlement delay has elapsed.
+/// Callable by anyone.
+#[no_mangle]
+pub extern "C" fn finalize_settlement() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_READY_TO_SETTLE && status != STATUS_DISPUTED {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_READY_TO_SETTLE));
+    }
+
+    require_settlement_unlocked(escrow_id);
+    let message = settle_escrow(escrow_id);
+
+    runtime::ret(
+        CLValue::from_t(message)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Records the calling signer's approval of a `ReadyToSettle` cosigned
+/// escrow. Settlement stays locked until both signers have approved.
+#[no_mangle]
+pub extern "C" fn approve_settlement() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let approved_key = require_signer(escrow_id);
+
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_READY_TO_SETTLE {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_READY_TO_SETTLE));
+    }
+
+    write_named_value(&approved_key, true);
+
+    emit_event("settlement_approved", escrow_id, &format!("signer:{}", runtime::get_caller()));
+}
+
+/// Lets either signer cancel a cosigned escrow, sending every participant
+/// their principal, including installments that have not reached the split
+/// yet.
+#[no_mangle]
+pub extern "C" fn veto_settlement() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    require_signer(escrow_id);
+
+    let is_completed: bool = read_named_value(&escrow_completed_key(escrow_id));
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(100));
+    }
+
+    let (total_refund, total_refunded) = cancel_with_full_refund(escrow_id);
+
+    emit_event("settlement_vetoed", escrow_id, &format!("signer:{},amount:{},total_refunded:{}", runtime::get_caller(), total_refund, total_refunded));
+}
+
+/// Pays a deferred settle-to-creator escrow out once its grace period is over.
+#[no_mangle]
+pub extern "C" fn finalize_complete() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let payout_pending_key = escrow_payout_pending_key(escrow_id);
+    if !read_named_value_or(&payout_pending_key, false) {
+        runtime::revert(casper_types::ApiError::User(ERR_NO_PENDING_PAYOUT));
+    }
+    let refundable_until: u64 = read_named_value(&escrow_refundable_until_key(escrow_id));
+    if now_millis() < refundable_until {
+        runtime::revert(casper_types::ApiError::User(ERR_GRACE_ACTIVE));
+    }
+
+    write_named_value(&payout_pending_key, false);
+    let total_staked: u64 = read_named_value(&escrow_total_staked_key(escrow_id));
+    let total_yield: u64 = read_named_value(&escrow_total_yield_key(escrow_id));
+    settle_cspr_to_creator(escrow_id, total_staked, total_yield);
+
+    runtime::ret(
+        CLValue::from_t(format!("Escrow {} paid {} to creator", escrow_id, total_staked + total_yield))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Lets the creator of a deferred settle-to-creator escrow refund everyone
+/// while the grace period is still running.
+#[no_mangle]
+pub extern "C" fn refund_after_complete() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    require_creator(escrow_id);
+
+    let payout_pending_key = escrow_payout_pending_key(escrow_id);
+    if !read_named_value_or(&payout_pending_key, false) {
+        runtime::revert(casper_types::ApiError::User(ERR_NO_PENDING_PAYOUT));
+    }
+    let refundable_until: u64 = read_named_value(&escrow_refundable_until_key(escrow_id));
+    if now_millis() >= refundable_until {
+        runtime::revert(casper_types::ApiError::User(ERR_GRACE_EXPIRED));
+    }
+
+    write_named_value(&payout_pending_key, false);
+    let (total_refund, total_refunded) = cancel_with_full_refund(escrow_id);
+
+    emit_event("refunded_after_complete", escrow_id, &format!("amount:{},total_refunded:{}", total_refund, total_refunded));
+}
+
+/// Lets a participant block settlement while the escrow is inside its
+/// settlement delay window.
+#[no_mangle]
+pub extern "C" fn raise_dispute() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let caller = runtime::get_caller();
+
+    // Joining is free, so only participants with credited stake may dispute.
+    let stake: u64 = read_named_value_or(&escrow_stake_key(escrow_id, caller), 0u64);
+    if stake == 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_PARTICIPANT));
+    }
+
+    let status_key = escrow_status_key(escrow_id);
+    let status: u8 = read_named_value_or(&status_key, STATUS_OPEN);
+    let settle_after: u64 = read_named_value_or(&escrow_settle_after_key(escrow_id), 0u64);
+    if status != STATUS_READY_TO_SETTLE || now_millis() >= settle_after {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_READY_TO_SETTLE));
+    }
+
+    set_status(escrow_id, STATUS_DISPUTED);
+
+    emit_event("dispute_raised", escrow_id, &format!("participant:{}", caller));
+}
+
+/// Casts the caller's vote to cancel a disputed escrow. Every participant
+/// with credited stake gets one vote; once the votes exceed the contract's
+/// threshold share of those same participants the escrow is cancelled and
+/// everyone is refunded, without waiting for the admin.
+#[no_mangle]
+pub extern "C" fn vote_cancel() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let caller = runtime::get_caller();
+
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_DISPUTED {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_DISPUTED));
+    }
+    let stake: u64 = read_named_value_or(&escrow_stake_key(escrow_id, caller), 0u64);
+    if stake == 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_PARTICIPANT));
+    }
+
+    let voted_key = escrow_cancel_voted_key(escrow_id, caller);
+    if runtime::has_key(&voted_key) {
+        runtime::revert(casper_types::ApiError::User(ERR_ALREADY_VOTED));
+    }
+    write_named_value(&voted_key, true);
+
+    let votes_key = escrow_cancel_votes_key(escrow_id);
+    let votes: u64 = read_named_value_or(&votes_key, 0u64) + 1;
+    write_named_value(&votes_key, votes);
+
+    emit_event("cancel_voted", escrow_id, &format!("voter:{},votes:{}", caller, votes));
+
+    let participants: Vec<AccountHash> = read_named_value(&escrow_participants_key(escrow_id));
+    let voter_count = participants
+        .into_iter()
+        .filter(|participant| read_named_value_or(&escrow_stake_key(escrow_id, *participant), 0u64) > 0)
+        .count() as u64;
+    let threshold_bps: u64 = read_named_value_or(CANCEL_VOTE_THRESHOLD_BPS_KEY, DEFAULT_CANCEL_VOTE_THRESHOLD_BPS);
+    if votes as u128 * BPS_DENOMINATOR as u128 > voter_count as u128 * threshold_bps as u128 {
+        let (total_refund, total_refunded) = cancel_with_full_refund(escrow_id);
+
+        emit_event("cancelled_by_vote", escrow_id, &format!("votes:{},amount:{},total_refunded:{}", votes, total_refund, total_refunded));
+    }
+}
+
+/// Emits a `nudge` event reminding the rest of the group that an open escrow
+/// still has unpaid slots. Rate-limited per participant by the contract's
+/// nudge interval; nothing else is written.
+#[no_mangle]
+pub extern "C" fn nudge() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let caller = runtime::get_caller();
+
+    let participants: Vec<AccountHash> = read_named_value(&escrow_participants_key(escrow_id));
+    if !participants.contains(&caller) {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_PARTICIPANT));
+    }
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_OPEN {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_OPEN));
+    }
+
+    let last_nudge_key = escrow_last_nudge_key(escrow_id, caller);
+    let nudge_interval: u64 = read_named_value_or(NUDGE_INTERVAL_KEY, DEFAULT_NUDGE_INTERVAL);
+    if runtime::has_key(&last_nudge_key) {
+        let last_nudge: u64 = read_named_value(&last_nudge_key);
+        if now_millis() < last_nudge.saturating_add(nudge_interval) {
+            runtime::revert(casper_types::ApiError::User(ERR_NUDGE_TOO_SOON));
+        }
+    }
+    write_named_value(&last_nudge_key, now_millis());
+
+    let remaining_slots = remaining_slots(escrow_id);
+
+    emit_event("nudge", escrow_id, &format!("from:{},remaining_slots:{}", caller, remaining_slots));
+}
+
+/// Number of split-sized slots not yet fully paid.
+fn remaining_slots(escrow_id: u64) -> u64 {
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let target_amount: u64 = read_named_value(&escrow_target_key(escrow_id));
+    let joined_count: u64 = read_named_value_or(&escrow_joined_count_key(escrow_id), 0u64);
+    target_amount.div_ceil(split).saturating_sub(joined_count)
+}
+
+/// Queues the caller for a slot on an open escrow whose slots are all paid.
+/// No funds are taken; the caller is invited with a `slot_open` event when a
+/// participant refunds.
+#[no_mangle]
+pub extern "C" fn join_waitlist() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let caller = runtime::get_caller();
+
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_OPEN && status != STATUS_READY_TO_SETTLE {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_OPEN));
+    }
+    if remaining_slots(escrow_id) > 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_FULL));
+    }
+
+    let waitlist_key = escrow_waitlist_key(escrow_id);
+    let mut waitlist: Vec<AccountHash> = read_named_value_or(&waitlist_key, Vec::new());
+    if waitlist.contains(&caller) {
+        runtime::revert(casper_types::ApiError::User(ERR_ALREADY_WAITLISTED));
+    }
+    if waitlist.len() >= MAX_WAITLIST_LENGTH {
+        runtime::revert(casper_types::ApiError::User(ERR_WAITLIST_FULL));
+    }
+    waitlist.push(caller);
+    let position = waitlist.len();
+    write_named_value(&waitlist_key, waitlist);
+
+    emit_event("waitlist_joined", escrow_id, &format!("account:{},position:{}", caller, position));
+}
+
+/// Pops the head of the escrow's waitlist, if any, and invites it to join.
+fn invite_next_waitlisted(escrow_id: u64) {
+    let waitlist_key = escrow_waitlist_key(escrow_id);
+    let mut waitlist: Vec<AccountHash> = read_named_value_or(&waitlist_key, Vec::new());
+    if waitlist.is_empty() {
+        return;
+    }
+    let invitee = waitlist.remove(0);
+    write_named_value(&waitlist_key, waitlist);
+
+    emit_event("slot_open", escrow_id, &format!("invitee:{}", invitee));
+}
+
+/// Returns the accounts waiting for a slot on the escrow, first in line first.
+#[no_mangle]
+pub extern "C" fn get_waitlist() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let waitlist: Vec<AccountHash> = read_named_value_or(&escrow_waitlist_key(escrow_id), Vec::new());
+
+    runtime::ret(
+        CLValue::from_t(waitlist)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Asks a conditional escrow's oracle whether its condition is met and, if
+/// so, records it so the escrow can settle. Callable by anyone; returns the
+/// oracle's answer.
+#[no_mangle]
+pub extern "C" fn check_condition() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let oracle_key = escrow_oracle_key(escrow_id);
+    if !runtime::has_key(&oracle_key) {
+        runtime::revert(casper_types::ApiError::User(ERR_NO_ORACLE));
+    }
+    let oracle: ContractHash = read_named_value(&oracle_key);
+    let condition_key: String = read_named_value(&escrow_condition_key(escrow_id));
+
+    let condition_met: bool = runtime::call_contract(
+        oracle,
+        EP_ORACLE_IS_CONDITION_MET,
+        runtime_args! { ARG_CONDITION_KEY => condition_key.clone() },
+    );
+    if condition_met {
+        write_named_value(&escrow_condition_met_key(escrow_id), true);
+
+        emit_event("condition_met", escrow_id, &format!("condition_key:{}", condition_key));
+    }
+
+    runtime::ret(
+        CLValue::from_t(condition_met)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Cancels a conditional escrow and refunds everyone when its deadline has
+/// passed without the oracle confirming the condition. Callable by anyone.
+#[no_mangle]
+pub extern "C" fn refund_unmet_condition() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    if !runtime::has_key(&escrow_oracle_key(escrow_id)) {
+        runtime::revert(casper_types::ApiError::User(ERR_NO_ORACLE));
+    }
+    let is_completed: bool = read_named_value(&escrow_completed_key(escrow_id));
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(100));
+    }
+    let condition_met: bool = read_named_value_or(&escrow_condition_met_key(escrow_id), false);
+    let deadline: u64 = read_named_value(&escrow_deadline_key(escrow_id));
+    if condition_met || deadline == 0 || now_millis() < deadline {
+        runtime::revert(casper_types::ApiError::User(ERR_CONDITION_REFUND_LOCKED));
+    }
+
+    let (total_refund, total_refunded) = cancel_with_full_refund(escrow_id);
+
+    emit_event("condition_refunded", escrow_id, &format!("amount:{},total_refunded:{}", total_refund, total_refunded));
+}
+
+/// Cancels and refunds an open escrow that has gone `idle_cancel_window`
+/// without a new participant. Callable by anyone; the caller is paid the
+/// idle cancel bounty from the fee pool, or whatever the pool has left.
+#[no_mangle]
+pub extern "C" fn auto_cancel_idle() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let caller = runtime::get_caller();
+
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_OPEN {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_OPEN));
+    }
+    let idle_cancel_window: u64 = read_named_value_or(IDLE_CANCEL_WINDOW_KEY, 0u64);
+    let created_at: u64 = read_named_value(&escrow_created_at_key(escrow_id));
+    let last_join_time: u64 = read_named_value_or(&escrow_last_join_time_key(escrow_id), created_at);
+    if idle_cancel_window == 0 || now_millis() < last_join_time.saturating_add(idle_cancel_window) {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_IDLE));
+    }
+
+    let (total_refund, total_refunded) = cancel_with_full_refund(escrow_id);
+
+    let idle_cancel_bounty: u64 = read_named_value_or(IDLE_CANCEL_BOUNTY_KEY, 0u64);
+    let bounty = match runtime::get_key(FEE_POOL_KEY).and_then(|key| key.into_uref()) {
+        Some(fee_pool) if idle_cancel_bounty > 0 => {
+            let pool_balance = system::get_purse_balance(fee_pool).unwrap_or_revert();
+            let bounty = U512::from(idle_cancel_bounty).min(pool_balance).as_u64();
+            if bounty > 0 {
+                system::transfer_from_purse_to_account(
+                    fee_pool,
+                    caller,
+                    U512::from(bounty),
+                    None
+                ).unwrap_or_revert_with(casper_types::ApiError::User(ERR_PAYOUT_TRANSFER_FAILED));
+            }
+            bounty
+        }
+        _ => 0,
+    };
+
+    emit_event("auto_cancelled", escrow_id, &format!("keeper:{},bounty:{},amount:{},total_refunded:{}", caller, bounty, total_refund, total_refunded));
+}
+
+/// Adds `amount` motes from `purse` to the fee pool that keeper bounties are
+/// paid from and returns a confirmation message. Callable by anyone.
+#[no_mangle]
+pub extern "C" fn fund_fee_pool() {
+    let amount: u64 = runtime::get_named_arg(ARG_AMOUNT);
+    let source_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+
+    let fee_pool = match runtime::get_key(FEE_POOL_KEY) {
+        Some(key) => key.into_uref().unwrap_or_revert(),
+        None => {
+            let fee_pool = system::create_purse();
+            runtime::put_key(FEE_POOL_KEY, fee_pool.into());
+            fee_pool
+        }
+    };
+    system::transfer_from_purse_to_purse(
+        source_purse,
+        fee_pool,
+        U512::from(amount),
+        None
+    ).unwrap_or_revert();
+
+    emit_contract_event("fee_pool_funded", &format!("from:{},amount:{}", runtime::get_caller(), amount));
+
+    runtime::ret(
+        CLValue::from_t(format!("Added {} to the fee pool", amount))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the fee pool's balance in motes.
+#[no_mangle]
+pub extern "C" fn get_fee_pool() {
+    let balance: u64 = match runtime::get_key(FEE_POOL_KEY).and_then(|key| key.into_uref()) {
+        Some(fee_pool) => system::get_purse_balance(fee_pool).unwrap_or_revert().as_u64(),
+        None => 0,
+    };
+
+    runtime::ret(
+        CLValue::from_t(balance)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Admin-only cancellation for escrows too large to refund in one call.
+/// Refunds up to `max_count` principal holders per call, resuming from a
+/// stored cursor. The escrow is REFUNDING between batches and is marked
+/// cancelled with the last one.
+#[no_mangle]
+pub extern "C" fn refund_batch() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let max_count: u8 = runtime::get_named_arg(ARG_MAX_COUNT);
+
+    require_admin();
+
+    let is_completed: bool = read_named_value(&escrow_completed_key(escrow_id));
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(100));
+    }
+    if max_count == 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_ZERO_AMOUNT));
+    }
+
+    let holders = principal_holders(escrow_id);
+    let cursor_key = escrow_refund_cursor_key(escrow_id);
+    let cursor: u64 = read_named_value_or(&cursor_key, 0u64);
+    let start = cursor as usize;
+    let end = (start + max_count as usize).min(holders.len());
+
+    let batch_refund = refund_principal(escrow_id, &holders[start..end]);
+    write_named_value(&cursor_key, end as u64);
+    let total_refunded = add_to_ledger_total(TOTAL_REFUNDED_KEY, batch_refund);
+
+    emit_event("refund_batch", escrow_id, &format!("refunded:{},remaining:{},amount:{},total_refunded:{}", end, holders.len() - end, batch_refund, total_refunded));
+
+    if end == holders.len() {
+        mark_cancelled(escrow_id);
+    } else if start == 0 {
+        set_status(escrow_id, STATUS_REFUNDING);
+    }
+}
+
+/// Admin-only support refund of one participant: sends their stake and any
+/// partial installments to their refund address without a leave fee and
+/// removes them from the escrow.
+#[no_mangle]
+pub extern "C" fn admin_refund_participant() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    require_admin();
+
+    let is_completed: bool = read_named_value(&escrow_completed_key(escrow_id));
+    if is_completed {
+        runtime::revert(casper_types::ApiError::User(100));
+    }
+    let staked_amount: u64 = read_named_value_or(&escrow_stake_key(escrow_id, participant), 0u64);
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let paid: u64 = read_named_value_or(&escrow_paid_key(escrow_id, participant), 0u64);
+    if staked_amount == 0 && paid == 0 {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_PARTICIPANT));
+    }
+
+    let refund_amount = refund_principal(escrow_id, &[participant]);
+    runtime::remove_key(&escrow_join_time_key(escrow_id, participant));
+
+    let participants_key = escrow_participants_key(escrow_id);
+    let mut participants: Vec<AccountHash> = read_named_value(&participants_key);
+    participants.retain(|account| *account != participant);
+    write_named_value(&participants_key, participants);
+    remove_from_account_escrows(participant, escrow_id);
+
+    let total_staked_key = escrow_total_staked_key(escrow_id);
+    let total_staked: u64 = read_named_value(&total_staked_key);
+    write_named_value(&total_staked_key, total_staked - staked_amount);
+
+    if staked_amount >= split {
+        let joined_count_key = escrow_joined_count_key(escrow_id);
+        let joined_count: u64 = read_named_value_or(&joined_count_key, 0u64);
+        write_named_value(&joined_count_key, joined_count.saturating_sub(1));
+        invite_next_waitlisted(escrow_id);
+    }
+
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status == STATUS_READY_TO_SETTLE {
+        set_status(escrow_id, STATUS_OPEN);
+        write_named_value(&escrow_settle_after_key(escrow_id), 0u64);
+    }
+
+    let total_refunded = add_to_ledger_total(TOTAL_REFUNDED_KEY, refund_amount);
+
+    emit_event("admin_refunded", escrow_id, &format!("participant:{},amount:{},total_refunded:{}", participant, refund_amount, total_refunded));
+}
+
+/// Lowers an open escrow's slot count to `num_participants` when the
+/// remaining slots will not fill. Existing payments are untouched; the target
+/// becomes `num_participants * split`, i.e. the contributions of the people
+/// who actually take part, and the escrow becomes settleable as soon as
+/// its stake covers that.
+#[no_mangle]
+pub extern "C" fn shrink_escrow() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let num_participants: u64 = runtime::get_named_arg(ARG_NUM_PARTICIPANTS);
+
+    require_creator(escrow_id);
+
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_OPEN {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_OPEN));
+    }
+    // Recipient amounts are fixed to sum to the target, so it cannot move.
+    if runtime::has_key(&escrow_recipients_key(escrow_id)) {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_RECIPIENTS));
+    }
+
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let target_key = escrow_target_key(escrow_id);
+    let target_amount: u64 = read_named_value(&target_key);
+    let joined_count: u64 = read_named_value_or(&escrow_joined_count_key(escrow_id), 0u64);
+    let new_target = num_participants
+        .checked_mul(split)
+        .unwrap_or_revert_with(casper_types::ApiError::User(ERR_INVALID_PARTICIPANT_COUNT));
+    if num_participants < MIN_PARTICIPANTS || num_participants < joined_count || new_target >= target_amount {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_PARTICIPANT_COUNT));
+    }
+
+    write_named_value(&target_key, new_target);
+
+    emit_event("escrow_shrunk", escrow_id, &format!("num_participants:{},old_target:{},target:{}", num_participants, target_amount, new_target));
+
+    let total_staked: u64 = read_named_value(&escrow_total_staked_key(escrow_id));
+    mark_ready_if_funded(escrow_id, total_staked);
+}
+
+/// Stops new joins and installments on the escrow until `unpause_escrow`.
+/// Refunds, withdrawals and settlement are unaffected. Callable by the
+/// creator or the escrow admin.
+#[no_mangle]
+pub extern "C" fn pause_escrow() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    require_creator_or_escrow_admin(escrow_id);
+    write_named_value(&escrow_paused_key(escrow_id), true);
+
+    emit_event("escrow_paused", escrow_id, &format!("by:{}", runtime::get_caller()));
+}
+
+#[no_mangle]
+pub extern "C" fn unpause_escrow() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    require_creator_or_escrow_admin(escrow_id);
+    write_named_value(&escrow_paused_key(escrow_id), false);
+
+    emit_event("escrow_unpaused", escrow_id, &format!("by:{}", runtime::get_caller()));
+}
+
+/// Settles a dispute on a managed escrow. With `cancel` the escrow is
+/// cancelled and everyone refunded; otherwise it goes back to
+/// `ReadyToSettle`. Only the escrow admin may call this, not the creator,
+/// who is paid on settlement.
+#[no_mangle]
+pub extern "C" fn resolve_dispute() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let cancel: bool = runtime::get_named_arg(ARG_CANCEL);
+    let caller = runtime::get_caller();
+
+    let escrow_admin_key = escrow_admin_key(escrow_id);
+    if !runtime::has_key(&escrow_admin_key) || caller != read_named_value::<AccountHash>(&escrow_admin_key) {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_ESCROW_ADMIN));
+    }
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_DISPUTED {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_DISPUTED));
+    }
+
+    if cancel {
+        let (total_refund, total_refunded) = cancel_with_full_refund(escrow_id);
+
+        emit_event("dispute_resolved", escrow_id, &format!("by:{},cancelled:true,amount:{},total_refunded:{}", caller, total_refund, total_refunded));
+    } else {
+        set_status(escrow_id, STATUS_READY_TO_SETTLE);
+
+        emit_event("dispute_resolved", escrow_id, &format!("by:{},cancelled:false", caller));
+    }
+}
+
+/// Returns the escrow's operational admin, if it has one.
+#[no_mangle]
+pub extern "C" fn get_escrow_admin() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let escrow_admin_key = escrow_admin_key(escrow_id);
+    let escrow_admin: Option<AccountHash> = if runtime::has_key(&escrow_admin_key) {
+        Some(read_named_value(&escrow_admin_key))
+    } else {
+        None
+    };
+
+    runtime::ret(
+        CLValue::from_t(escrow_admin)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Reopens an open escrow whose deadline passed without it filling, giving
+/// it the future `deadline`. Existing payments and participants are kept.
+/// Creator-only, and only while nobody has taken a refund since the deadline.
+#[no_mangle]
+pub extern "C" fn revive_escrow() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let new_deadline: u64 = runtime::get_named_arg(ARG_DEADLINE);
+
+    require_creator(escrow_id);
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    let deadline: u64 = read_named_value(&escrow_deadline_key(escrow_id));
+    if status != STATUS_OPEN || deadline == 0 || now_millis() < deadline {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_EXPIRED));
+    }
+    if read_named_value_or(&escrow_expiry_refunded_key(escrow_id), false) {
+        runtime::revert(casper_types::ApiError::User(ERR_EXPIRY_REFUNDS_CLAIMED));
+    }
+    if new_deadline <= now_millis() {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_DEADLINE));
+    }
+
+    write_named_value(&escrow_deadline_key(escrow_id), new_deadline);
+
+    emit_event("escrow_revived", escrow_id, &format!("previous_deadline:{},deadline:{}", deadline, new_deadline));
+}
+
+#[no_mangle]
+pub extern "C" fn update_memo() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let new_memo: String = runtime::get_named_arg(ARG_NEW_MEMO);
+
+    require_creator(escrow_id);
+    require_memo_length(&new_memo);
+
+    write_named_value(&escrow_memo_key(escrow_id), new_memo.clone());
+
+    emit_event("memo_updated", escrow_id, &format!("memo:{}", new_memo));
+}
+
+#[no_mangle]
+pub extern "C" fn withdraw() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    let completed_key = escrow_completed_key(escrow_id);
+    let completed_storage_key = runtime::get_key(&completed_key).unwrap_or_revert();
+    let completed_uref = completed_storage_key.into_uref().unwrap_or_revert();
+    let is_completed: bool = storage::read(completed_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    if !is_completed {
+        runtime::revert(casper_types::ApiError::User(101));
+    }
+
+    if pays_out_on_settlement(escrow_id) {
+        runtime::revert(casper_types::ApiError::User(ERR_PAID_OUT_ON_SETTLEMENT));
+    }
+
+    if now_millis() < unlock_time(escrow_id) {
+        runtime::revert(casper_types::ApiError::User(ERR_STAKE_LOCKED));
+    }
+
+    let withdrawn_key = escrow_withdrawn_key(escrow_id, participant);
+    if let Some(key) = runtime::get_key(&withdrawn_key) {
+        let uref = key.into_uref().unwrap_or_revert();
+        let already_withdrawn: bool = storage::read(uref)
+            .unwrap_or_revert()
+            .unwrap_or_revert();
+        if already_withdrawn {
+            runtime::revert(casper_types::ApiError::User(102));
+        }
+    }
+
+    let stake_key = escrow_stake_key(escrow_id, participant);
+    let stake_storage_key = runtime::get_key(&stake_key).unwrap_or_revert();
+    let stake_uref = stake_storage_key.into_uref().unwrap_or_revert();
+    let staked_amount: u64 = storage::read(stake_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    if staked_amount == 0 {
+        runtime::revert(casper_types::ApiError::User(106));
+    }
+
+    let total_staked_key = escrow_total_staked_key(escrow_id);
+    let total_staked_storage_key = runtime::get_key(&total_staked_key).unwrap_or_revert();
+    let total_staked_uref = total_staked_storage_key.into_uref().unwrap_or_revert();
+    let total_staked: u64 = storage::read(total_staked_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    let total_yield_key = escrow_total_yield_key(escrow_id);
+    let total_yield_storage_key = runtime::get_key(&total_yield_key).unwrap_or_revert();
+    let total_yield_uref = total_yield_storage_key.into_uref().unwrap_or_revert();
+    let total_yield: u64 = storage::read(total_yield_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    let participant_yield = if total_staked > 0 {
+        (staked_amount * total_yield) / total_staked
+    } else {
+        0u64
+    };
+
+    let participant_yield_key = escrow_participant_yield_key(escrow_id, participant);
+    let participant_yield_uref = storage::new_uref(participant_yield);
+    runtime::put_key(&participant_yield_key, participant_yield_uref.into());
+
+    let target_purse = runtime::get_named_arg::<casper_types::URef>(ARG_PURSE);
+
+    let escrow_purse_key = escrow_purse_key(escrow_id);
+    let escrow_purse_storage_key = runtime::get_key(&escrow_purse_key).unwrap_or_revert();
+    let escrow_purse = escrow_purse_storage_key.into_uref().unwrap_or_revert();
+
+    system::transfer_from_purse_to_purse(
+        escrow_purse,
+        target_purse,
+        U512::from(staked_amount),
+        None
+    ).unwrap_or_revert();
+
+    if participant_yield > 0 {
+        let yield_purse_key = escrow_yield_purse_key(escrow_id);
+        let yield_purse_storage_key = runtime::get_key(&yield_purse_key).unwrap_or_revert();
+        let yield_purse = yield_purse_storage_key.into_uref().unwrap_or_revert();
+
+        system::transfer_from_purse_to_purse(
+            yield_purse,
+            target_purse,
+            U512::from(participant_yield),
+            None
+        ).unwrap_or_revert();
+    }
+
+    let withdrawn_uref = storage::new_uref(true);
+    runtime::put_key(&withdrawn_key, withdrawn_uref.into());
+
+    let liquid_balance_key = escrow_liquid_balance_key(escrow_id, participant);
+    if let Some(key) = runtime::get_key(&liquid_balance_key) {
+        let uref = key.into_uref().unwrap_or_revert();
+        storage::write(uref, 0u64);
+    }
+
+    let total_withdrawal = staked_amount + participant_yield;
+    let total_settled = add_to_ledger_total(TOTAL_SETTLED_KEY, total_withdrawal);
+
+    emit_event("withdrawn", escrow_id, &format!("participant:{},principal:{},yield:{},total:{},total_settled:{}", participant, staked_amount, participant_yield, total_withdrawal, total_settled));
+
+    runtime::ret(
+        CLValue::from_t(format!("Withdrawn {} (principal: {}, yield: {}) from escrow {}", total_withdrawal, staked_amount, participant_yield, escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn get_participants() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let participants_key = escrow_participants_key(escrow_id);
+    let participants_storage_key = runtime::get_key(&participants_key).unwrap_or_revert();
+    let participants_uref = participants_storage_key.into_uref().unwrap_or_revert();
+    let participants: Vec<AccountHash> = storage::read(participants_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    runtime::ret(
+        CLValue::from_t(participants)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Sets the caller's display name, shown to every escrow they join. Names
+/// are self-chosen and advisory only. An empty name clears it.
+#[no_mangle]
+pub extern "C" fn set_display_name() {
+    let display_name: String = runtime::get_named_arg(ARG_DISPLAY_NAME);
+    let caller = runtime::get_caller();
+
+    if display_name.len() > MAX_DISPLAY_NAME_LENGTH || display_name.chars().any(char::is_control) {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_DISPLAY_NAME));
+    }
+
+    let display_name_key = display_name_key(caller);
+    if display_name.is_empty() {
+        runtime::remove_key(&display_name_key);
+    } else {
+        write_named_value(&display_name_key, display_name);
+    }
+}
+
+/// Returns `(participant, display_name)` for each participant of the escrow
+/// who has set a name, in participant order.
+#[no_mangle]
+pub extern "C" fn get_participant_names() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let participants: Vec<AccountHash> = read_named_value(&escrow_participants_key(escrow_id));
+    let names: Vec<(AccountHash, String)> = participants
+        .into_iter()
+        .filter_map(|participant| {
+            let display_name_key = display_name_key(participant);
+            runtime::has_key(&display_name_key).then(|| (participant, read_named_value(&display_name_key)))
+        })
+        .collect();
+
+    runtime::ret(
+        CLValue::from_t(names)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn get_stake() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    let stake_key = escrow_stake_key(escrow_id, participant);
+    let stake_storage_key = runtime::get_key(&stake_key).unwrap_or_revert();
+    let stake_uref = stake_storage_key.into_uref().unwrap_or_revert();
+    let staked_amount: u64 = storage::read(stake_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    runtime::ret(
+        CLValue::from_t(staked_amount)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn get_liquid_balance() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    let liquid_balance_key = escrow_liquid_balance_key(escrow_id, participant);
+    let liquid_storage_key = runtime::get_key(&liquid_balance_key).unwrap_or_revert();
+    let liquid_uref = liquid_storage_key.into_uref().unwrap_or_revert();
+    let liquid_amount: u64 = storage::read(liquid_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    runtime::ret(
+        CLValue::from_t(liquid_amount)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn get_yield() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    let stake_key = escrow_stake_key(escrow_id, participant);
+    let stake_storage_key = runtime::get_key(&stake_key).unwrap_or_revert();
+    let stake_uref = stake_storage_key.into_uref().unwrap_or_revert();
+    let staked_amount: u64 = storage::read(stake_uref)
+.unwrap_or_revert()
+.unwrap_or_revert();
+let total_staked_key = escrow_total_staked_key(escrow_id);
+let total_staked_storage_key = runtime::get_key(&total_staked_key).unwrap_or_revert();
+let total_staked_uref = total_staked_storage_key.into_uref().unwrap_or_revert();
+let total_staked: u64 = storage::read(total_staked_uref)
+    .unwrap_or_revert()
+    .unwrap_or_revert();
+
+let total_yield_key = escrow_total_yield_key(escrow_id);
+let total_yield_storage_key = runtime::get_key(&total_yield_key).unwrap_or_revert();
+let total_yield_uref = total_yield_storage_key.into_uref().unwrap_or_revert();
+let total_yield: u64 = storage::read(total_yield_uref)
+    .unwrap_or_revert()
+    .unwrap_or_revert();
+
+let participant_yield = if total_staked > 0 {
+    (staked_amount * total_yield) / total_staked
+} else {
+    0u64
+};
+
+runtime::ret(
+    CLValue::from_t(participant_yield)
+        .unwrap_or_revert(),
+);
+}
+#[no_mangle]
+pub extern "C" fn get_escrow_balance() {
+let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+let escrow_purse_key = escrow_purse_key(escrow_id);
+let escrow_purse_storage_key = runtime::get_key(&escrow_purse_key).unwrap_or_revert();
+let escrow_purse = escrow_purse_storage_key.into_uref().unwrap_or_revert();
+let balance = system::get_purse_balance(escrow_purse).unwrap_or_revert();
+let balance_u64: u64 = balance.as_u64();
+
+runtime::ret(
+    CLValue::from_t(balance_u64)
+        .unwrap_or_revert(),
+);
+}
+#[no_mangle]
+pub extern "C" fn get_participant_yield() {
+let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+let participant_yield_key = escrow_participant_yield_key(escrow_id, participant);
+
+match runtime::get_key(&participant_yield_key) {
+    Some(key) => {
+        let uref = key.into_uref().unwrap_or_revert();
+        let yield_amount: u64 = storage::read(uref)
+            .unwrap_or_revert()
+            .unwrap_or_revert();
+        
+        runtime::ret(
+            CLValue::from_t(yield_amount)
+                .unwrap_or_revert(),
+        );
+    }
+    None => {
+        runtime::ret(
+            CLValue::from_t(0u64)
+                .unwrap_or_revert(),
+        );
+    }
+}
+}
+#[no_mangle]
+pub extern "C" fn get_contract_purse() {
+let purse = get_main_purse();
+runtime::ret(
+    CLValue::from_t(purse)
+        .unwrap_or_revert(),
+);
+}
+/// Returns the escrow deadline in milliseconds (the `get_blocktime()` unit),
+/// or 0 if the escrow was created without one.
+#[no_mangle]
+pub extern "C" fn get_deadline() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let deadline_key = escrow_deadline_key(escrow_id);
+    let deadline: u64 = match runtime::get_key(&deadline_key) {
+        Some(key) => {
+            let uref = key.into_uref().unwrap_or_revert();
+            storage::read(uref)
+                .unwrap_or_revert()
+                .unwrap_or_revert()
+        }
+        None => 0u64,
+    };
+
+    runtime::ret(
+        CLValue::from_t(deadline)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the earliest block time (milliseconds) at which the escrow can
+/// settle, accounting for both the settlement timelock and the wait after the
+/// last join.
+#[no_mangle]
+pub extern "C" fn get_earliest_settle_time() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    runtime::ret(
+        CLValue::from_t(earliest_settle_time(escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the block time (milliseconds) after which the escrow can be
+/// finalized, or 0 if it has not become ready to settle.
+#[no_mangle]
+pub extern "C" fn get_settle_after() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let settle_after: u64 = read_named_value_or(&escrow_settle_after_key(escrow_id), 0u64);
+
+    runtime::ret(
+        CLValue::from_t(settle_after)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Sums the staked amount across the creator's open escrows. The loop is
+/// bounded by `max_escrows_per_creator`.
+#[no_mangle]
+pub extern "C" fn get_creator_exposure() {
+    let creator: AccountHash = runtime::get_named_arg(ARG_CREATOR);
+
+    let creator_escrows: Vec<u64> = read_named_value_or(&creator_escrows_key(creator), Vec::new());
+    let mut exposure = 0u64;
+    for escrow_id in creator_escrows {
+        let total_staked: u64 = read_named_value_or(&escrow_total_staked_key(escrow_id), 0u64);
+        exposure = exposure.saturating_add(total_staked);
+    }
+
+    runtime::ret(
+        CLValue::from_t(exposure)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Portfolio view of `account`: maps each of its open escrows as creator,
+/// then each escrow it has joined, to `(role, status, staked)`. An escrow the
+/// account both created and joined is reported once, as `ROLE_CREATOR`.
+/// At most `MAX_PAGE_SIZE` positions are returned.
+#[no_mangle]
+pub extern "C" fn get_account_positions() {
+    let account: AccountHash = runtime::get_named_arg(ARG_ACCOUNT);
+
+    let created: Vec<u64> = read_named_value_or(&creator_escrows_key(account), Vec::new());
+    let joined: Vec<u64> = read_named_value_or(&account_escrows_key(account), Vec::new());
+    let roles = created
+        .into_iter()
+        .map(|escrow_id| (escrow_id, ROLE_CREATOR))
+        .chain(joined.into_iter().map(|escrow_id| (escrow_id, ROLE_PARTICIPANT)));
+
+    let mut positions: BTreeMap<u64, (u8, u8, u64)> = BTreeMap::new();
+    for (escrow_id, role) in roles {
+        if positions.len() as u64 >= MAX_PAGE_SIZE {
+            break;
+        }
+        if positions.contains_key(&escrow_id) {
+            continue;
+        }
+        let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+        let staked: u64 = read_named_value_or(&escrow_stake_key(escrow_id, account), 0u64);
+        positions.insert(escrow_id, (role, status, staked));
+    }
+
+    runtime::ret(
+        CLValue::from_t(positions)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns `(expected_amount, is_filled)` per slot. Slots are the escrow's
+/// per-participant amount repeated up to the target, with any remainder in the
+/// last slot; the first `joined_count` slots are filled. At most
+/// `MAX_SPLIT_SCHEDULE_SLOTS` entries are returned.
+#[no_mangle]
+pub extern "C" fn get_split_schedule() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let target_amount: u64 = read_named_value(&escrow_target_key(escrow_id));
+    let joined_count: u64 = read_named_value_or(&escrow_joined_count_key(escrow_id), 0u64);
+
+    let num_slots = target_amount.div_ceil(split).min(MAX_SPLIT_SCHEDULE_SLOTS);
+    let mut schedule: Vec<(u64, bool)> = Vec::new();
+    for slot in 0..num_slots {
+        schedule.push((slot_amount(split, target_amount, slot), slot < joined_count));
+    }
+
+    runtime::ret(
+        CLValue::from_t(schedule)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Expected payment for `slot`: the split, or whatever remains of the target
+/// for the last slot.
+fn slot_amount(split: u64, target_amount: u64, slot: u64) -> u64 {
+    (target_amount - split * slot).min(split)
+}
+
+/// Returns what the unfilled slot at `slot_index` costs, laid out as in
+/// `get_split_schedule`. Reverts if the index is past the last slot or the
+/// slot is already filled.
+#[no_mangle]
+pub extern "C" fn get_slot_amount() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let slot_index: u64 = runtime::get_named_arg(ARG_SLOT_INDEX);
+
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let target_amount: u64 = read_named_value(&escrow_target_key(escrow_id));
+    let joined_count: u64 = read_named_value_or(&escrow_joined_count_key(escrow_id), 0u64);
+
+    if slot_index >= target_amount.div_ceil(split) {
+        runtime::revert(casper_types::ApiError::User(ERR_SLOT_OUT_OF_RANGE));
+    }
+    if slot_index < joined_count {
+        runtime::revert(casper_types::ApiError::User(ERR_SLOT_FILLED));
+    }
+
+    runtime::ret(
+        CLValue::from_t(slot_amount(split, target_amount, slot_index))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Sends the caller's future refunds from an open escrow to `new_refund`.
+#[no_mangle]
+pub extern "C" fn update_refund_address() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let new_refund: AccountHash = runtime::get_named_arg(ARG_NEW_REFUND);
+    let caller = runtime::get_caller();
+
+    let participants: Vec<AccountHash> = read_named_value(&escrow_participants_key(escrow_id));
+    if !participants.contains(&caller) {
+        runtime::revert(casper_types::ApiError::User(ERR_NOT_PARTICIPANT));
+    }
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    if status != STATUS_OPEN || is_terminal(escrow_id) {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_OPEN));
+    }
+
+    write_named_value(&escrow_refund_address_key(escrow_id, caller), new_refund);
+
+    emit_event("refund_address_updated", escrow_id, &format!("participant:{},refund_address:{}", caller, new_refund));
+}
+
+/// Returns the account the participant's refunds are paid to.
+#[no_mangle]
+pub extern "C" fn get_refund_address() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    runtime::ret(
+        CLValue::from_t(refund_address(escrow_id, participant))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Sets an app-defined metadata value on the caller's escrow. Keys are
+/// 1-64 bytes, values up to 256, and an escrow holds at most 20 keys.
+#[no_mangle]
+pub extern "C" fn set_metadata() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let key: String = runtime::get_named_arg(ARG_KEY);
+    let value: String = runtime::get_named_arg(ARG_VALUE);
+
+    require_creator(escrow_id);
+    if key.is_empty() || key.len() > MAX_METADATA_KEY_LENGTH || value.len() > MAX_METADATA_VALUE_LENGTH {
+        runtime::revert(casper_types::ApiError::User(ERR_INVALID_METADATA));
+    }
+
+    let metadata_keys_key = escrow_metadata_keys_key(escrow_id);
+    let mut metadata_keys: Vec<String> = read_named_value_or(&metadata_keys_key, Vec::new());
+    if !metadata_keys.contains(&key) {
+        if metadata_keys.len() >= MAX_METADATA_ENTRIES {
+            runtime::revert(casper_types::ApiError::User(ERR_INVALID_METADATA));
+        }
+        metadata_keys.push(key.clone());
+        write_named_value(&metadata_keys_key, metadata_keys);
+    }
+    write_named_value(&escrow_metadata_key(escrow_id, &key), value);
+
+    emit_event("metadata_set", escrow_id, &format!("key:{}", key));
+}
+
+/// Returns a metadata value, empty if the key was never set.
+#[no_mangle]
+pub extern "C" fn get_metadata() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let key: String = runtime::get_named_arg(ARG_KEY);
+
+    let value: String = read_named_value_or(&escrow_metadata_key(escrow_id, &key), String::new());
+
+    runtime::ret(
+        CLValue::from_t(value)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the gas rebate each staked participant receives on settlement.
+#[no_mangle]
+pub extern "C" fn get_gas_rebate() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let gas_rebate: u64 = read_named_value_or(&escrow_gas_rebate_key(escrow_id), 0u64);
+
+    runtime::ret(
+        CLValue::from_t(gas_rebate)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns how many more escrows the caller can create before hitting the
+/// creation rate limit, or `u64::MAX` if there is none.
+#[no_mangle]
+pub extern "C" fn get_creation_allowance() {
+    let creation_rate_limit: u64 = read_named_value_or(CREATION_RATE_LIMIT_KEY, 0u64);
+    let allowance = if creation_rate_limit == 0 {
+        u64::MAX
+    } else {
+        let (_, created) = creation_window(runtime::get_caller());
+        creation_rate_limit.saturating_sub(created)
+    };
+
+    runtime::ret(
+        CLValue::from_t(allowance)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the decimals of the currency escrows hold, for formatting
+/// amounts. Every escrow holds native CSPR, so this is always 9.
+#[no_mangle]
+pub extern "C" fn get_decimals() {
+    runtime::ret(
+        CLValue::from_t(CSPR_DECIMALS)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the purse the creator's proceeds settle into, if one was set.
+#[no_mangle]
+pub extern "C" fn get_settlement_purse() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let settlement_purse = runtime::get_key(&escrow_settlement_purse_key(escrow_id))
+        .and_then(|key| key.into_uref());
+
+    runtime::ret(
+        CLValue::from_t(settlement_purse)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the escrow's encrypted memo bytes, empty if it has none.
+#[no_mangle]
+pub extern "C" fn get_encrypted_memo() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let encrypted_memo: Bytes = read_named_value_or(&escrow_encrypted_memo_key(escrow_id), Bytes::new());
+
+    runtime::ret(
+        CLValue::from_t(encrypted_memo)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn get_escrow_paused() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let paused: bool = read_named_value_or(&escrow_paused_key(escrow_id), false);
+
+    runtime::ret(
+        CLValue::from_t(paused)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the yield rounding dust routed away when the escrow completed.
+#[no_mangle]
+pub extern "C" fn get_dust() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let dust: u64 = read_named_value_or(&escrow_dust_key(escrow_id), 0u64);
+
+    runtime::ret(
+        CLValue::from_t(dust)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the number of `vote_cancel` votes cast on the escrow.
+#[no_mangle]
+pub extern "C" fn get_cancel_votes() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let votes: u64 = read_named_value_or(&escrow_cancel_votes_key(escrow_id), 0u64);
+
+    runtime::ret(
+        CLValue::from_t(votes)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the creation deposit still held for the escrow.
+#[no_mangle]
+pub extern "C" fn get_creation_deposit() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let deposit: u64 = read_named_value_or(&escrow_deposit_key(escrow_id), 0u64);
+
+    runtime::ret(
+        CLValue::from_t(deposit)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the creation deposit to the creator once the escrow is terminal.
+#[no_mangle]
+pub extern "C" fn claim_deposit() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    require_creator(escrow_id);
+    if !is_terminal(escrow_id) {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_TERMINAL));
+    }
+
+    let creator = runtime::get_caller();
+    let deposit = release_deposit(escrow_id, creator);
+
+    emit_event("deposit_claimed", escrow_id, &format!("creator:{},amount:{}", creator, deposit));
+}
+
+/// Sends the creation deposit of an abandoned escrow, one still not terminal
+/// `deposit_timeout` after creation, to the fee receiver. Callable by anyone.
+#[no_mangle]
+pub extern "C" fn forfeit_deposit() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let created_at: u64 = read_named_value(&escrow_created_at_key(escrow_id));
+    let deposit_timeout: u64 = read_named_value_or(DEPOSIT_TIMEOUT_KEY, DEFAULT_DEPOSIT_TIMEOUT);
+    if is_terminal(escrow_id) || now_millis() < created_at.saturating_add(deposit_timeout) {
+        runtime::revert(casper_types::ApiError::User(ERR_DEPOSIT_NOT_FORFEITABLE));
+    }
+
+    let fee_receiver: AccountHash = read_named_value(FEE_RECEIVER_KEY);
+    let deposit = release_deposit(escrow_id, fee_receiver);
+
+    emit_event("deposit_forfeited", escrow_id, &format!("fee_receiver:{},amount:{}", fee_receiver, deposit));
+}
+
+/// Removes a terminal escrow's named keys once nothing is left to pay out:
+/// both purses are empty, the creation deposit is released and no creator
+/// payout is pending. Callable by the admin, or by the creator when the
+/// contract allows it. Events, idempotency records and recurrence links are
+/// kept.
+#[no_mangle]
+pub extern "C" fn purge_escrow() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let admin: AccountHash = read_named_value(ADMIN_KEY);
+    if runtime::get_caller() != admin {
+        if !read_named_value_or(CREATOR_CAN_PURGE_KEY, false) {
+            runtime::revert(casper_types::ApiError::User(ERR_NOT_ADMIN));
+        }
+        require_creator(escrow_id);
+    }
+
+    let yield_purse = runtime::get_key(&escrow_yield_purse_key(escrow_id))
+        .unwrap_or_revert()
+        .into_uref()
+        .unwrap_or_revert();
+    let holds_funds = !system::get_purse_balance(get_escrow_purse(escrow_id)).unwrap_or_revert().is_zero()
+        || !system::get_purse_balance(yield_purse).unwrap_or_revert().is_zero()
+        || read_named_value_or(&escrow_deposit_key(escrow_id), 0u64) > 0
+        || read_named_value_or(&escrow_payout_pending_key(escrow_id), false);
+    if !is_terminal(escrow_id) || holds_funds {
+        runtime::revert(casper_types::ApiError::User(ERR_ESCROW_NOT_PURGEABLE));
+    }
+
+    let status: u8 = read_named_value(&escrow_status_key(escrow_id));
+    let status_key = status_escrows_key(status);
+    let mut status_escrows: Vec<u64> = read_named_value_or(&status_key, Vec::new());
+    status_escrows.retain(|id| *id != escrow_id);
+    write_named_value(&status_key, status_escrows);
+    remove_from_creator_escrows(escrow_id);
+    remove_from_tag_escrows(escrow_id);
+
+    let mut accounts: Vec<AccountHash> = read_named_value(&escrow_participants_key(escrow_id));
+    let payers: Vec<AccountHash> = read_named_value_or(&escrow_payers_key(escrow_id), Vec::new());
+    for payer in payers {
+        if !accounts.contains(&payer) {
+            accounts.push(payer);
+        }
+    }
+    for account in accounts {
+        remove_from_account_escrows(account, escrow_id);
+        for key in [
+            escrow_stake_key(escrow_id, account),
+            escrow_liquid_balance_key(escrow_id, account),
+            escrow_participant_yield_key(escrow_id, account),
+            escrow_withdrawn_key(escrow_id, account),
+            escrow_paid_key(escrow_id, account),
+            escrow_cancel_voted_key(escrow_id, account),
+            escrow_last_nudge_key(escrow_id, account),
+            escrow_refund_address_key(escrow_id, account),
+            escrow_join_time_key(escrow_id, account),
+        ] {
+            runtime::remove_key(&key);
+        }
+    }
+
+    let metadata_keys: Vec<String> = read_named_value_or(&escrow_metadata_keys_key(escrow_id), Vec::new());
+    for key in metadata_keys {
+        runtime::remove_key(&escrow_metadata_key(escrow_id, &key));
+    }
+
+    let escrow_keys: [fn(u64) -> String; 54] = [
+        escrow_key,
+        escrow_joined_key,
+        escrow_participants_key,
+        escrow_total_staked_key,
+        escrow_total_yield_key,
+        escrow_completed_key,
+        escrow_purse_key,
+        escrow_target_key,
+        escrow_yield_purse_key,
+        escrow_deadline_key,
+        escrow_payers_key,
+        escrow_joined_count_key,
+        escrow_status_key,
+        escrow_settle_after_key,
+        escrow_creator_key,
+        escrow_exclude_creator_key,
+        escrow_cosigner_key,
+        escrow_creator_approved_key,
+        escrow_cosigner_approved_key,
+        escrow_settle_to_creator_key,
+        escrow_min_stake_duration_key,
+        escrow_completed_at_key,
+        escrow_created_at_key,
+        escrow_deposit_key,
+        escrow_deposit_purse_key,
+        escrow_cancel_votes_key,
+        escrow_dust_key,
+        escrow_oracle_key,
+        escrow_condition_key,
+        escrow_condition_met_key,
+        escrow_keep_excess_key,
+        escrow_open_pot_key,
+        escrow_metadata_keys_key,
+        escrow_refund_cursor_key,
+        escrow_paused_key,
+        escrow_encrypted_memo_key,
+        escrow_waitlist_key,
+        escrow_recurring_key,
+        escrow_gas_rebate_key,
+        escrow_gas_rebates_paid_key,
+        escrow_settlement_purse_key,
+        escrow_last_join_time_key,
+        escrow_settle_partial_key,
+        escrow_expiry_refunded_key,
+        escrow_admin_key,
+        escrow_recipients_key,
+        escrow_grace_refund_key,
+        escrow_payout_pending_key,
+        escrow_refundable_until_key,
+        escrow_gating_nft_key,
+        escrow_tags_key,
+        escrow_max_accumulated_key,
+        escrow_charity_key,
+        escrow_memo_key,
+    ];
+    for key in escrow_keys {
+        runtime::remove_key(&key(escrow_id));
+    }
+
+    emit_event("escrow_purged", escrow_id, &format!("status:{}", status));
+}
+
+/// Returns when withdrawals open (completion time plus the escrow's minimum
+/// stake duration), or 0 while the escrow has not completed.
+#[no_mangle]
+pub extern "C" fn get_unlock_time() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    runtime::ret(
+        CLValue::from_t(unlock_time(escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns everything a countdown needs in one call, keyed `created_at`,
+/// `deadline`, `now` and `remaining`, all in milliseconds like the deadline.
+/// `remaining` is 0 once the deadline has passed and `NO_DEADLINE_REMAINING`
+/// when the escrow has none.
+#[no_mangle]
+pub extern "C" fn get_timing() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let created_at: u64 = read_named_value_or(&escrow_created_at_key(escrow_id), 0u64);
+    let deadline: u64 = read_named_value(&escrow_deadline_key(escrow_id));
+    let now = now_millis();
+    let remaining = if deadline == 0 {
+        NO_DEADLINE_REMAINING
+    } else {
+        deadline.saturating_sub(now)
+    };
+
+    let mut timing: BTreeMap<String, u64> = BTreeMap::new();
+    timing.insert("created_at".to_string(), created_at);
+    timing.insert("deadline".to_string(), deadline);
+    timing.insert("now".to_string(), now);
+    timing.insert("remaining".to_string(), remaining);
+
+    runtime::ret(
+        CLValue::from_t(timing)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns what is still outstanding for the escrow in one call:
+/// `settle_pending` (1 while ready to settle) with its `settle_after` time,
+/// `refunds_pending` and `refunds_pending_total` for holders a batch refund
+/// has not reached yet, and `payout_pending` (1 while a deferred creator
+/// payout waits) with its `refundable_until` time. Principal never leaves
+/// the escrow purse for staking, so there is no unstaking to report.
+#[no_mangle]
+pub extern "C" fn get_pending_operations() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let status: u8 = read_named_value_or(&escrow_status_key(escrow_id), STATUS_OPEN);
+    let settle_pending = status == STATUS_READY_TO_SETTLE;
+    let settle_after = if settle_pending { earliest_settle_time(escrow_id) } else { 0 };
+
+    let mut refunds_pending = 0u64;
+    let mut refunds_pending_total = 0u64;
+    if status == STATUS_REFUNDING {
+        let split: u64 = read_named_value(&escrow_key(escrow_id));
+        let cursor: u64 = read_named_value_or(&escrow_refund_cursor_key(escrow_id), 0u64);
+        for participant in principal_holders(escrow_id).into_iter().skip(cursor as usize) {
+            let owed = principal_owed(escrow_id, participant, split);
+            if owed > 0 {
+                refunds_pending += 1;
+                refunds_pending_total += owed;
+            }
+        }
+    }
+
+    let payout_pending: bool = read_named_value_or(&escrow_payout_pending_key(escrow_id), false);
+    let refundable_until = if payout_pending {
+        read_named_value(&escrow_refundable_until_key(escrow_id))
+    } else {
+        0u64
+    };
+
+    let mut pending: BTreeMap<String, u64> = BTreeMap::new();
+    pending.insert("settle_pending".to_string(), settle_pending as u64);
+    pending.insert("settle_after".to_string(), settle_after);
+    pending.insert("refunds_pending".to_string(), refunds_pending);
+    pending.insert("refunds_pending_total".to_string(), refunds_pending_total);
+    pending.insert("payout_pending".to_string(), payout_pending as u64);
+    pending.insert("refundable_until".to_string(), refundable_until);
+
+    runtime::ret(
+        CLValue::from_t(pending)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns up to `limit` (capped at `MAX_PAGE_SIZE`) ids of escrows in
+/// `status`, starting at `offset`. Order changes as escrows move between
+/// statuses, so pages are not stable across transitions.
+#[no_mangle]
+pub extern "C" fn get_escrows_by_status() {
+    let status: u8 = runtime::get_named_arg(ARG_STATUS);
+    let offset: u64 = runtime::get_named_arg(ARG_OFFSET);
+    let limit: u64 = runtime::get_named_arg(ARG_LIMIT);
+
+    let escrows: Vec<u64> = read_named_value_or(&status_escrows_key(status), Vec::new());
+    let page: Vec<u64> = escrows
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit.min(MAX_PAGE_SIZE) as usize)
+        .collect();
+
+    runtime::ret(
+        CLValue::from_t(page)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns up to `limit` (capped at `MAX_PAGE_SIZE`) ids of escrows tagged
+/// `tag`, oldest first, starting at `offset`. Purged escrows drop out.
+#[no_mangle]
+pub extern "C" fn get_escrows_by_tag() {
+    let tag: String = runtime::get_named_arg(ARG_TAG);
+    let offset: u64 = runtime::get_named_arg(ARG_OFFSET);
+    let limit: u64 = runtime::get_named_arg(ARG_LIMIT);
+
+    let escrows: Vec<u64> = read_named_value_or(&tag_escrows_key(&tag), Vec::new());
+    let page: Vec<u64> = escrows
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit.min(MAX_PAGE_SIZE) as usize)
+        .collect();
+
+    runtime::ret(
+        CLValue::from_t(page)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the recipient schedule, empty for escrows without one.
+#[no_mangle]
+pub extern "C" fn get_recipients() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let recipients: Vec<(AccountHash, u64)> = read_named_value_or(&escrow_recipients_key(escrow_id), Vec::new());
+
+    runtime::ret(
+        CLValue::from_t(recipients)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns whether an escrow with this id has been created. Never reverts.
+#[no_mangle]
+pub extern "C" fn escrow_exists() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    runtime::ret(
+        CLValue::from_t(runtime::has_key(&escrow_key(escrow_id)))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns how many participants have paid their full split. Installment
+/// payers still below the split are not counted.
+#[no_mangle]
+pub extern "C" fn get_participant_count() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let joined_count: u64 = read_named_value(&escrow_joined_count_key(escrow_id));
+
+    runtime::ret(
+        CLValue::from_t(joined_count)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn get_template() {
+    let creator: AccountHash = runtime::get_named_arg(ARG_CREATOR);
+    let template_name: String = runtime::get_named_arg(ARG_TEMPLATE_NAME);
+
+    let template_key = template_key(creator, &template_name);
+    if !runtime::has_key(&template_key) {
+        runtime::revert(casper_types::ApiError::User(ERR_TEMPLATE_NOT_FOUND));
+    }
+    let template: (u64, u64, bool) = read_named_value(&template_key);
+
+    runtime::ret(
+        CLValue::from_t(template)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the CEP-78 contract gating participation, or `None` if the escrow is open to all.
+#[no_mangle]
+pub extern "C" fn get_gating_nft() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let gating_nft_key = escrow_gating_nft_key(escrow_id);
+    let gating_nft: Option<ContractHash> = if runtime::has_key(&gating_nft_key) {
+        Some(read_named_value(&gating_nft_key))
+    } else {
+        None
+    };
+
+    runtime::ret(
+        CLValue::from_t(gating_nft)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the contract-wide configuration keyed by named key, read live from
+/// storage so it reflects admin changes made after install.
+#[no_mangle]
+pub extern "C" fn get_config() {
+    let admin: AccountHash = read_named_value(ADMIN_KEY);
+    let fee_receiver: AccountHash = read_named_value_or(FEE_RECEIVER_KEY, admin);
+
+    let mut config: BTreeMap<String, String> = BTreeMap::new();
+    config.insert(ADMIN_KEY.to_string(), admin.to_string());
+    config.insert(FEE_RECEIVER_KEY.to_string(), fee_receiver.to_string());
+    config.insert(LEAVE_FEE_BPS_KEY.to_string(), read_named_value_or(LEAVE_FEE_BPS_KEY, 0u64).to_string());
+    config.insert(LATE_LEAVE_FEE_BPS_KEY.to_string(), read_named_value_or(LATE_LEAVE_FEE_BPS_KEY, 0u64).to_string());
+    config.insert(SETTLEMENT_DELAY_KEY.to_string(), read_named_value_or(SETTLEMENT_DELAY_KEY, 0u64).to_string());
+    config.insert(
+        MAX_ESCROWS_PER_CREATOR_KEY.to_string(),
+        read_named_value_or(MAX_ESCROWS_PER_CREATOR_KEY, DEFAULT_MAX_ESCROWS_PER_CREATOR).to_string(),
+    );
+    config.insert(POST_COMPLETE_GRACE_KEY.to_string(), read_named_value_or(POST_COMPLETE_GRACE_KEY, 0u64).to_string());
+    config.insert(CREATION_DEPOSIT_KEY.to_string(), read_named_value_or(CREATION_DEPOSIT_KEY, 0u64).to_string());
+    config.insert(
+        DEPOSIT_TIMEOUT_KEY.to_string(),
+        read_named_value_or(DEPOSIT_TIMEOUT_KEY, DEFAULT_DEPOSIT_TIMEOUT).to_string(),
+    );
+    config.insert(
+        CANCEL_VOTE_THRESHOLD_BPS_KEY.to_string(),
+        read_named_value_or(CANCEL_VOTE_THRESHOLD_BPS_KEY, DEFAULT_CANCEL_VOTE_THRESHOLD_BPS).to_string(),
+    );
+    config.insert(
+        DEFAULT_DEADLINE_OFFSET_KEY.to_string(),
+        read_named_value_or(DEFAULT_DEADLINE_OFFSET_KEY, 0u64).to_string(),
+    );
+    config.insert(NUDGE_INTERVAL_KEY.to_string(), read_named_value_or(NUDGE_INTERVAL_KEY, DEFAULT_NUDGE_INTERVAL).to_string());
+    if runtime::has_key(DUST_RECIPIENT_KEY) {
+        let dust_recipient: AccountHash = read_named_value(DUST_RECIPIENT_KEY);
+        config.insert(DUST_RECIPIENT_KEY.to_string(), dust_recipient.to_string());
+    }
+    if runtime::has_key(VERIFIER_KEY) {
+        let verifier: ContractHash = read_named_value(VERIFIER_KEY);
+        config.insert(VERIFIER_KEY.to_string(), verifier.to_string());
+    }
+    if runtime::has_key(PRICE_ORACLE_KEY) {
+        let price_oracle: ContractHash = read_named_value(PRICE_ORACLE_KEY);
+        config.insert(PRICE_ORACLE_KEY.to_string(), price_oracle.to_string());
+    }
+    config.insert(ANYONE_CAN_CLONE_KEY.to_string(), read_named_value_or(ANYONE_CAN_CLONE_KEY, false).to_string());
+    config.insert(CREATOR_CAN_PURGE_KEY.to_string(), read_named_value_or(CREATOR_CAN_PURGE_KEY, false).to_string());
+    config.insert(IDLE_CANCEL_WINDOW_KEY.to_string(), read_named_value_or(IDLE_CANCEL_WINDOW_KEY, 0u64).to_string());
+    config.insert(IDLE_CANCEL_BOUNTY_KEY.to_string(), read_named_value_or(IDLE_CANCEL_BOUNTY_KEY, 0u64).to_string());
+    config.insert(
+        MIN_SETTLE_DELAY_AFTER_LAST_JOIN_KEY.to_string(),
+        read_named_value_or(MIN_SETTLE_DELAY_AFTER_LAST_JOIN_KEY, 0u64).to_string(),
+    );
+    config.insert(CREATION_RATE_LIMIT_KEY.to_string(), read_named_value_or(CREATION_RATE_LIMIT_KEY, 0u64).to_string());
+    config.insert(
+        CREATION_RATE_WINDOW_KEY.to_string(),
+        read_named_value_or(CREATION_RATE_WINDOW_KEY, DEFAULT_CREATION_RATE_WINDOW).to_string(),
+    );
+    config.insert(EVENTS_ENABLED_KEY.to_string(), events_enabled().to_string());
+
+    runtime::ret(
+        CLValue::from_t(config)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Health check for monitors: returns `PING_RESPONSE`, or reverts if a
+/// required named key is missing. Reads only; per-escrow balance drift is
+/// checked by `reconcile`.
+#[no_mangle]
+pub extern "C" fn ping() {
+    if REQUIRED_KEYS.iter().any(|key| !runtime::has_key(key)) {
+        runtime::revert(casper_types::ApiError::User(ERR_UNHEALTHY));
+    }
+
+    runtime::ret(
+        CLValue::from_t(PING_RESPONSE)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the block time at which the contract was installed.
+#[no_mangle]
+pub extern "C" fn get_install_time() {
+    let installed_at: u64 = read_named_value(INSTALLED_AT_KEY);
+
+    runtime::ret(
+        CLValue::from_t(installed_at)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the most principal the escrow may hold, `u64::MAX` when uncapped.
+#[no_mangle]
+pub extern "C" fn get_accumulated_cap() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    runtime::ret(
+        CLValue::from_t(accumulated_cap(escrow_id))
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the escrow's target expressed in the price oracle's quote
+/// currency, rounded down. Informational only. Reverts with
+/// `ERR_ORACLE_UNAVAILABLE` when no oracle is configured or it has no price.
+#[no_mangle]
+pub extern "C" fn get_value_in_quote() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    if !runtime::has_key(PRICE_ORACLE_KEY) {
+        runtime::revert(casper_types::ApiError::User(ERR_ORACLE_UNAVAILABLE));
+    }
+    let price_oracle: ContractHash = read_named_value(PRICE_ORACLE_KEY);
+    let price: Option<u64> = runtime::call_contract(price_oracle, EP_PRICE_ORACLE_GET_CSPR_PRICE, runtime_args! {});
+    let price = price.unwrap_or_revert_with(casper_types::ApiError::User(ERR_ORACLE_UNAVAILABLE));
+
+    let target_amount: u64 = read_named_value(&escrow_target_key(escrow_id));
+    let value = u128::from(target_amount) * u128::from(price) / 10u128.pow(u32::from(CSPR_DECIMALS));
+    let value = u64::try_from(value).ok().unwrap_or_revert_with(casper_types::ApiError::User(ERR_LEDGER_OVERFLOW));
+
+    runtime::ret(
+        CLValue::from_t(value)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the escrow's `(charity, charity_bps)`, if it donates on settlement.
+#[no_mangle]
+pub extern "C" fn get_charity_config() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let charity_key = escrow_charity_key(escrow_id);
+    let charity: Option<(AccountHash, u64)> = if runtime::has_key(&charity_key) {
+        Some(read_named_value(&charity_key))
+    } else {
+        None
+    };
+
+    runtime::ret(
+        CLValue::from_t(charity)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the verifier contract joins are checked against, if any.
+#[no_mangle]
+pub extern "C" fn get_verifier() {
+    let verifier: Option<ContractHash> = if runtime::has_key(VERIFIER_KEY) {
+        Some(read_named_value(VERIFIER_KEY))
+    } else {
+        None
+    };
+
+    runtime::ret(
+        CLValue::from_t(verifier)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the contract versions the package has had, oldest first.
+#[no_mangle]
+pub extern "C" fn get_versions() {
+    let versions: Vec<u32> = read_named_value(VERSIONS_KEY);
+
+    runtime::ret(
+        CLValue::from_t(versions)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns this contract's hash, for callers that need to reference it back.
+#[no_mangle]
+pub extern "C" fn get_self_hash() {
+    let self_hash: ContractHash = read_named_value(SELF_HASH_KEY);
+
+    runtime::ret(
+        CLValue::from_t(self_hash)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the ids of every period in the escrow's recurrence chain, oldest
+/// first. A non-recurring escrow's chain is just its own id.
+#[no_mangle]
+pub extern "C" fn get_recurrence_chain() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    let mut first = escrow_id;
+    while runtime::has_key(&escrow_prev_key(first)) {
+        first = read_named_value(&escrow_prev_key(first));
+    }
+    let mut chain = vec![first];
+    let mut current = first;
+    while runtime::has_key(&escrow_next_key(current)) {
+        current = read_named_value(&escrow_next_key(current));
+        chain.push(current);
+    }
+
+    runtime::ret(
+        CLValue::from_t(chain)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the entry point schema recorded at install.
+#[no_mangle]
+pub extern "C" fn get_abi() {
+    let abi: String = read_named_value(ABI_KEY);
+
+    runtime::ret(
+        CLValue::from_t(abi)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns what `participant` would be paid by `refund` right now: their
+/// stake and any partial installments, less the current leave fee.
+#[no_mangle]
+pub extern "C" fn preview_leave_refund() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    let staked_amount: u64 = read_named_value_or(&escrow_stake_key(escrow_id, participant), 0u64);
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let paid: u64 = read_named_value_or(&escrow_paid_key(escrow_id, participant), 0u64);
+    let partial_amount = if paid >= split { 0u64 } else { paid };
+    let refund_amount = staked_amount + partial_amount;
+    let payout = refund_amount.saturating_sub(leave_fee(escrow_id, participant, refund_amount));
+
+    runtime::ret(
+        CLValue::from_t(payout)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Returns the leave fee charged on refunds, in basis points. With a late
+/// leave fee configured this is the rate right after joining.
+#[no_mangle]
+pub extern "C" fn get_leave_fee() {
+    let leave_fee_bps: u64 = read_named_value_or(LEAVE_FEE_BPS_KEY, 0u64);
+
+    runtime::ret(
+        CLValue::from_t(leave_fee_bps)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn get_events_enabled() {
+    runtime::ret(
+        CLValue::from_t(events_enabled())
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn get_participant_paid() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+    let participant: AccountHash = runtime::get_named_arg(ARG_PARTICIPANT);
+
+    let paid: u64 = read_named_value_or(&escrow_paid_key(escrow_id, participant), 0u64);
+
+    runtime::ret(
+        CLValue::from_t(paid)
+            .unwrap_or_revert(),
+    );
+}
+
+/// Admin check that the escrow purse holds exactly the principal the ledger
+/// attributes to it: unwithdrawn stakes (none once paid out on settlement)
+/// plus installments below the split.
+/// Emits `drift_detected` on a mismatch and returns `(expected, actual)`.
+#[no_mangle]
+pub extern "C" fn reconcile() {
+    let escrow_id: u64 = runtime::get_named_arg(ARG_ESCROW_ID);
+
+    require_admin();
+
+    let split: u64 = read_named_value(&escrow_key(escrow_id));
+    let completed: bool = read_named_value(&escrow_completed_key(escrow_id));
+    let paid_out = completed
+        && pays_out_on_settlement(escrow_id)
+        && !read_named_value_or(&escrow_payout_pending_key(escrow_id), false);
+    let mut expected = 0u64;
+    for holder in principal_holders(escrow_id) {
+        let withdrawn: bool = read_named_value_or(&escrow_withdrawn_key(escrow_id, holder), false);
+        if !withdrawn && !paid_out {
+            expected += read_named_value_or(&escrow_stake_key(escrow_id, holder), 0u64);
+        }
+        let paid: u64 = read_named_value_or(&escrow_paid_key(escrow_id, holder), 0u64);
+        if paid < split {
+            expected += paid;
+        }
+    }
+
+    let actual = system::get_purse_balance(get_escrow_purse(escrow_id))
+        .unwrap_or_revert()
+        .as_u64();
+
+    if actual != expected {
+        emit_event("drift_detected", escrow_id, &format!("expected:{},actual:{}", expected, actual));
+    }
+
+    runtime::ret(
+        CLValue::from_t((expected, actual))
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn transfer_admin() {
+    let new_admin: AccountHash = runtime::get_named_arg(ARG_NEW_ADMIN);
+
+    require_admin();
+
+    let previous_admin: AccountHash = read_named_value(ADMIN_KEY);
+    write_named_value(ADMIN_KEY, new_admin);
+
+    emit_contract_event("admin_transferred", &format!("previous_admin:{},new_admin:{}", previous_admin, new_admin));
+}
+
+/// Admin-only emergency halt. Stronger than pausing an escrow: creation,
+/// joins, payments and settlement all revert with `ERR_FROZEN`, while
+/// refunds, leaves, cancellations and withdrawals stay open so funds can exit.
+#[no_mangle]
+pub extern "C" fn freeze() {
+    require_admin();
+
+    write_named_value(FROZEN_KEY, true);
+
+    emit_contract_event("frozen", &format!("by:{}", runtime::get_caller()));
+}
+
+#[no_mangle]
+pub extern "C" fn unfreeze() {
+    require_admin();
+
+    write_named_value(FROZEN_KEY, false);
+
+    emit_contract_event("unfrozen", &format!("by:{}", runtime::get_caller()));
+}
+
+#[no_mangle]
+pub extern "C" fn is_frozen() {
+    let frozen: bool = read_named_value_or(FROZEN_KEY, false);
+
+    runtime::ret(
+        CLValue::from_t(frozen)
+            .unwrap_or_revert(),
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn get_admin() {
+    let admin: AccountHash = read_named_value(ADMIN_KEY);
+
+    runtime::ret(
+        CLValue::from_t(admin)
+            .unwrap_or_revert(),
+    );
+}
+
+// ================= INSTALL CONTRACT =================
+#[no_mangle]
+pub extern "C" fn call() {
+let mut entry_points = EntryPoints::new();
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CREATE_ESCROW,
+        create_escrow_params(),
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CREATE_RECURRING_ESCROW,
+        create_escrow_params(),
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_RECURRENCE_CHAIN,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::List(Box::new(CLType::U64)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CREATE_ESCROW_MULTI_RECIPIENT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_AMOUNT, CLType::U64),
+            Parameter::new(ARG_TARGET_AMOUNT, CLType::U64),
+            Parameter::new(
+                ARG_RECIPIENTS,
+                CLType::List(Box::new(CLType::Tuple2([Box::new(CLType::Key), Box::new(CLType::U64)]))),
+            ),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CREATE_ESCROW_BATCH,
+        vec![
+            Parameter::new(
+                ARG_ESCROWS,
+                CLType::List(Box::new(CLType::Tuple3([
+                    Box::new(CLType::U64),
+                    Box::new(CLType::U64),
+                    Box::new(CLType::U64),
+                ]))),
+            ),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::List(Box::new(CLType::String)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_SAVE_TEMPLATE,
+        vec![
+            Parameter::new(ARG_TEMPLATE_NAME, CLType::String),
+            Parameter::new(ARG_NUM_PARTICIPANTS, CLType::U64),
+            Parameter::new(ARG_DEADLINE_OFFSET, CLType::U64),
+            Parameter::new(ARG_EXCLUDE_CREATOR, CLType::Bool),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CLONE_ESCROW,
+        vec![
+            Parameter::new(ARG_SOURCE_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CREATE_FROM_TEMPLATE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_TEMPLATE_NAME, CLType::String),
+            Parameter::new(ARG_TARGET_AMOUNT, CLType::U64),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_DELETE_TEMPLATE,
+        vec![
+            Parameter::new(ARG_TEMPLATE_NAME, CLType::String),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_JOIN_ESCROW,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_IDEMPOTENCY_KEY, CLType::String),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_STAKE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_AMOUNT, CLType::U64),
+            Parameter::new(ARG_PARTICIPANT, CLType::Key),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_PAY_INSTALLMENT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_AMOUNT, CLType::U64),
+            Parameter::new(ARG_PURSE, CLType::URef),
+            Parameter::new(ARG_IDEMPOTENCY_KEY, CLType::String),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_FUND_SLOTS,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_BENEFICIARIES, CLType::List(Box::new(CLType::Key))),
+            Parameter::new(ARG_AMOUNT, CLType::U64),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_REFUND,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_PARTICIPANT, CLType::Key),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_DEPOSIT_YIELD,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_AMOUNT, CLType::U64),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_COMPLETE_ESCROW,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_FINALIZE_SETTLEMENT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_TRANSFER_STAKE,
+        vec![
+            Parameter::new(ARG_SOURCE_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_SETTLE_AT_DEADLINE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_RAISE_DISPUTE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_APPROVE_SETTLEMENT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_VETO_SETTLEMENT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_FINALIZE_COMPLETE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_REFUND_AFTER_COMPLETE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_UPDATE_MEMO,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_NEW_MEMO, CLType::String),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_RECONCILE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Tuple2([Box::new(CLType::U64), Box::new(CLType::U64)]),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_WITHDRAW,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_PARTICIPANT, CLType::Key),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_PARTICIPANTS,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::List(Box::new(CLType::Key)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_SET_DISPLAY_NAME,
+        vec![Parameter::new(ARG_DISPLAY_NAME, CLType::String)],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_PARTICIPANT_NAMES,
+        vec![Parameter::new(ARG_ESCROW_ID, CLType::U64)],
+        CLType::List(Box::new(CLType::Tuple2([Box::new(CLType::Key), Box::new(CLType::String)]))),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_STAKE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_PARTICIPANT, CLType::Key),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_LIQUID_BALANCE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_PARTICIPANT, CLType::Key),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_YIELD,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_PARTICIPANT, CLType::Key),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_ESCROW_BALANCE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_PARTICIPANT_YIELD,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_PARTICIPANT, CLType::Key),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_CONTRACT_PURSE,
+        vec![],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_DEADLINE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_SETTLE_AFTER,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_CREATOR_EXPOSURE,
+        vec![
+            Parameter::new(ARG_CREATOR, CLType::Key),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_SPLIT_SCHEDULE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::List(Box::new(CLType::Tuple2([Box::new(CLType::U64), Box::new(CLType::Bool)]))),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_SLOT_AMOUNT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_SLOT_INDEX, CLType::U64),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_CREATION_DEPOSIT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CLAIM_DEPOSIT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_FORFEIT_DEPOSIT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CREATE_OPEN_POT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_TARGET_AMOUNT, CLType::U64),
+            Parameter::new(ARG_MIN_CONTRIBUTION, CLType::U64),
+            Parameter::new(ARG_MAX_ACCUMULATED, CLType::U64),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CONTRIBUTE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_AMOUNT, CLType::U64),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_POT_BALANCE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_CONTRIBUTIONS,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_OFFSET, CLType::U64),
+            Parameter::new(ARG_LIMIT, CLType::U64),
+        ],
+        CLType::List(Box::new(CLType::Tuple2([Box::new(CLType::Key), Box::new(CLType::U64)]))),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_SET_METADATA,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_KEY, CLType::String),
+            Parameter::new(ARG_VALUE, CLType::String),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_METADATA,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_KEY, CLType::String),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_PENDING_OPERATIONS,
+        vec![Parameter::new(ARG_ESCROW_ID, CLType::U64)],
+        CLType::Map {
+            key: Box::new(CLType::String),
+            value: Box::new(CLType::U64),
+        },
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_CHARITY_CONFIG,
+        vec![Parameter::new(ARG_ESCROW_ID, CLType::U64)],
+        CLType::Option(Box::new(CLType::Tuple2([Box::new(CLType::Key), Box::new(CLType::U64)]))),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_VALUE_IN_QUOTE,
+        vec![Parameter::new(ARG_ESCROW_ID, CLType::U64)],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_SETTLE_BATCH,
+        vec![Parameter::new(ARG_ESCROW_IDS, CLType::List(Box::new(CLType::U64)))],
+        CLType::List(Box::new(CLType::Bool)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CHECK_MEMBERSHIPS,
+        vec![
+            Parameter::new(ARG_ESCROW_IDS, CLType::List(Box::new(CLType::U64))),
+            Parameter::new(ARG_ACCOUNT, CLType::Key),
+        ],
+        CLType::List(Box::new(CLType::Bool)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_REVIVE_ESCROW,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_DEADLINE, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_PREVIEW_LEAVE_REFUND,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_PARTICIPANT, CLType::Key),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_RESOLVE_DISPUTE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_CANCEL, CLType::Bool),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_ESCROW_ADMIN,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Option(Box::new(CLType::Key)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_ADMIN_REFUND_PARTICIPANT,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_PARTICIPANT, CLType::Key),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CAN_JOIN,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_ACCOUNT, CLType::Key),
+        ],
+        CLType::Tuple2([Box::new(CLType::Bool), Box::new(CLType::U8)]),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_PURGE_ESCROW,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_SHRINK_ESCROW,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_NUM_PARTICIPANTS, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_VOTE_CANCEL,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_CANCEL_VOTES,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_NUDGE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_JOIN_WAITLIST,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_WAITLIST,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::List(Box::new(CLType::Key)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_DUST,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_CHECK_CONDITION,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Bool,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_REFUND_UNMET_CONDITION,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_AUTO_CANCEL_IDLE,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_FUND_FEE_POOL,
+        vec![
+            Parameter::new(ARG_AMOUNT, CLType::U64),
+            Parameter::new(ARG_PURSE, CLType::URef),
+        ],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_FEE_POOL,
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_REFUND_BATCH,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+            Parameter::new(ARG_MAX_COUNT, CLType::U8),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_PAUSE_ESCROW,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_UNPAUSE_ESCROW,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_ESCROW_PAUSED,
+        vec![
+            Parameter::new(ARG_ESCROW_ID, CLType::U64),
+        ],
+        CLType::Bool,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
 );
-}
-/// ================= INSTALL CONTRACT =================
-#[no_mangle]
-pub extern "C" fn call() {
-let mut entry_points = EntryPoints::new();
+
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_CREATE_ESCROW,
+        EP_GET_TIMING,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
-            Parameter::new(ARG_AMOUNT, CLType::U64),
-            Parameter::new(ARG_TARGET_AMOUNT, CLType::U64),
         ],
-        CLType::String,
+        CLType::Map {
+            key: Box::new(CLType::String),
+            value: Box::new(CLType::U64),
+        },
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -745,11 +6299,11 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_JOIN_ESCROW,
+        EP_GET_ENCRYPTED_MEMO,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
         ],
-        CLType::Unit,
+        CLType::List(Box::new(CLType::U8)),
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -758,14 +6312,12 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_STAKE,
+        EP_UPDATE_REFUND_ADDRESS,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
-            Parameter::new(ARG_AMOUNT, CLType::U64),
-            Parameter::new(ARG_PARTICIPANT, CLType::Key),
-            Parameter::new(ARG_PURSE, CLType::URef),
+            Parameter::new(ARG_NEW_REFUND, CLType::Key),
         ],
-        CLType::String,
+        CLType::Unit,
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -774,13 +6326,12 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_REFUND,
+        EP_GET_REFUND_ADDRESS,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
             Parameter::new(ARG_PARTICIPANT, CLType::Key),
-            Parameter::new(ARG_PURSE, CLType::URef),
         ],
-        CLType::String,
+        CLType::Key,
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -789,13 +6340,11 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_DEPOSIT_YIELD,
+        EP_GET_GAS_REBATE,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
-            Parameter::new(ARG_AMOUNT, CLType::U64),
-            Parameter::new(ARG_PURSE, CLType::URef),
         ],
-        CLType::String,
+        CLType::U64,
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -804,11 +6353,33 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_COMPLETE_ESCROW,
+        EP_GET_CREATION_ALLOWANCE,
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_DECIMALS,
+        vec![],
+        CLType::U8,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_SETTLEMENT_PURSE,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
         ],
-        CLType::String,
+        CLType::Option(Box::new(CLType::URef)),
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -817,13 +6388,11 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_WITHDRAW,
+        EP_PREVIEW_SETTLEMENT,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
-            Parameter::new(ARG_PARTICIPANT, CLType::Key),
-            Parameter::new(ARG_PURSE, CLType::URef),
         ],
-        CLType::String,
+        CLType::Tuple3([Box::new(CLType::U64), Box::new(CLType::U64), Box::new(CLType::U64)]),
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -832,11 +6401,11 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_GET_PARTICIPANTS,
+        EP_GET_UNLOCK_TIME,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
         ],
-        CLType::List(Box::new(CLType::Key)),
+        CLType::U64,
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -845,12 +6414,41 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_GET_STAKE,
+        EP_GET_ESCROWS_BY_TAG,
+        vec![
+            Parameter::new(ARG_TAG, CLType::String),
+            Parameter::new(ARG_OFFSET, CLType::U64),
+            Parameter::new(ARG_LIMIT, CLType::U64),
+        ],
+        CLType::List(Box::new(CLType::U64)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_ESCROWS_BY_STATUS,
+        vec![
+            Parameter::new(ARG_STATUS, CLType::U8),
+            Parameter::new(ARG_OFFSET, CLType::U64),
+            Parameter::new(ARG_LIMIT, CLType::U64),
+        ],
+        CLType::List(Box::new(CLType::U64)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_RECIPIENTS,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
-            Parameter::new(ARG_PARTICIPANT, CLType::Key),
         ],
-        CLType::U64,
+        CLType::List(Box::new(CLType::Tuple2([Box::new(CLType::Key), Box::new(CLType::U64)]))),
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -859,12 +6457,11 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_GET_LIQUID_BALANCE,
+        EP_ESCROW_EXISTS,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
-            Parameter::new(ARG_PARTICIPANT, CLType::Key),
         ],
-        CLType::U64,
+        CLType::Bool,
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
@@ -873,10 +6470,9 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_GET_YIELD,
+        EP_GET_PARTICIPANT_COUNT,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
-            Parameter::new(ARG_PARTICIPANT, CLType::Key),
         ],
         CLType::U64,
         EntryPointAccess::Public,
@@ -887,10 +6483,46 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_GET_ESCROW_BALANCE,
+        EP_GET_GATING_NFT,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
         ],
+        CLType::Option(Box::new(CLType::ByteArray(32))),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_TEMPLATE,
+        vec![
+            Parameter::new(ARG_CREATOR, CLType::Key),
+            Parameter::new(ARG_TEMPLATE_NAME, CLType::String),
+        ],
+        CLType::Tuple3([Box::new(CLType::U64), Box::new(CLType::U64), Box::new(CLType::Bool)]),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_EVENTS_ENABLED,
+        vec![],
+        CLType::Bool,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_LEAVE_FEE,
+        vec![],
         CLType::U64,
         EntryPointAccess::Public,
         EntryPointType::Called,
@@ -900,7 +6532,98 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_GET_PARTICIPANT_YIELD,
+        EP_GET_ABI,
+        vec![],
+        CLType::String,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_PING,
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_INSTALL_TIME,
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_VERSIONS,
+        vec![],
+        CLType::List(Box::new(CLType::U32)),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_ACCUMULATED_CAP,
+        vec![Parameter::new(ARG_ESCROW_ID, CLType::U64)],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_VERIFIER,
+        vec![],
+        CLType::Option(Box::new(CLType::ByteArray(32))),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_SELF_HASH,
+        vec![],
+        CLType::ByteArray(32),
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_CONFIG,
+        vec![],
+        CLType::Map {
+            key: Box::new(CLType::String),
+            value: Box::new(CLType::String),
+        },
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_PARTICIPANT_PAID,
         vec![
             Parameter::new(ARG_ESCROW_ID, CLType::U64),
             Parameter::new(ARG_PARTICIPANT, CLType::Key),
@@ -914,30 +6637,184 @@ entry_points.add_entry_point(
 
 entry_points.add_entry_point(
     EntryPoint::new(
-        EP_GET_CONTRACT_PURSE,
+        EP_TRANSFER_ADMIN,
+        vec![
+            Parameter::new(ARG_NEW_ADMIN, CLType::Key),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_ADMIN,
         vec![],
-        CLType::URef,
+        CLType::Key,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_FREEZE,
+        vec![],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_UNFREEZE,
+        vec![],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_ACCOUNT_POSITIONS,
+        vec![Parameter::new(ARG_ACCOUNT, CLType::Key)],
+        CLType::Map {
+            key: Box::new(CLType::U64),
+            value: Box::new(CLType::Tuple3([
+                Box::new(CLType::U8),
+                Box::new(CLType::U8),
+                Box::new(CLType::U64),
+            ])),
+        },
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_GET_EARLIEST_SETTLE_TIME,
+        vec![Parameter::new(ARG_ESCROW_ID, CLType::U64)],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Called,
+    )
+    .into(),
+);
+
+entry_points.add_entry_point(
+    EntryPoint::new(
+        EP_IS_FROZEN,
+        vec![],
+        CLType::Bool,
         EntryPointAccess::Public,
         EntryPointType::Called,
     )
     .into(),
 );
 
-let named_keys = NamedKeys::new();
+let admin: AccountHash = runtime::try_get_named_arg(ARG_ADMIN).unwrap_or_else(runtime::get_caller);
+let settlement_delay: u64 = runtime::try_get_named_arg(ARG_SETTLEMENT_DELAY).unwrap_or(0);
+let max_escrows_per_creator: u64 = runtime::try_get_named_arg(ARG_MAX_ESCROWS_PER_CREATOR).unwrap_or(DEFAULT_MAX_ESCROWS_PER_CREATOR);
+let events_enabled: bool = runtime::try_get_named_arg(ARG_EVENTS_ENABLED).unwrap_or(true);
+let leave_fee_bps: u64 = runtime::try_get_named_arg(ARG_LEAVE_FEE_BPS).unwrap_or(0);
+let late_leave_fee_bps: u64 = runtime::try_get_named_arg(ARG_LATE_LEAVE_FEE_BPS).unwrap_or(0);
+if leave_fee_bps > BPS_DENOMINATOR || late_leave_fee_bps > BPS_DENOMINATOR {
+    runtime::revert(casper_types::ApiError::User(ERR_INVALID_BPS));
+}
+let fee_receiver: AccountHash = runtime::try_get_named_arg(ARG_FEE_RECEIVER).unwrap_or(admin);
+let post_complete_grace: u64 = runtime::try_get_named_arg(ARG_POST_COMPLETE_GRACE).unwrap_or(0);
+let creation_deposit: u64 = runtime::try_get_named_arg(ARG_CREATION_DEPOSIT).unwrap_or(0);
+let deposit_timeout: u64 = runtime::try_get_named_arg(ARG_DEPOSIT_TIMEOUT).unwrap_or(DEFAULT_DEPOSIT_TIMEOUT);
+let default_deadline_offset: u64 = runtime::try_get_named_arg(ARG_DEFAULT_DEADLINE_OFFSET).unwrap_or(0);
+let nudge_interval: u64 = runtime::try_get_named_arg(ARG_NUDGE_INTERVAL).unwrap_or(DEFAULT_NUDGE_INTERVAL);
+let anyone_can_clone: bool = runtime::try_get_named_arg(ARG_ANYONE_CAN_CLONE).unwrap_or(false);
+let creator_can_purge: bool = runtime::try_get_named_arg(ARG_CREATOR_CAN_PURGE).unwrap_or(false);
+let creation_rate_limit: u64 = runtime::try_get_named_arg(ARG_CREATION_RATE_LIMIT).unwrap_or(0);
+let idle_cancel_window: u64 = runtime::try_get_named_arg(ARG_IDLE_CANCEL_WINDOW).unwrap_or(0);
+let idle_cancel_bounty: u64 = runtime::try_get_named_arg(ARG_IDLE_CANCEL_BOUNTY).unwrap_or(0);
+let min_settle_delay_after_last_join: u64 = runtime::try_get_named_arg(ARG_MIN_SETTLE_DELAY_AFTER_LAST_JOIN).unwrap_or(0);
+let creation_rate_window: u64 = runtime::try_get_named_arg(ARG_CREATION_RATE_WINDOW).unwrap_or(DEFAULT_CREATION_RATE_WINDOW);
+let cancel_vote_threshold_bps: u64 = runtime::try_get_named_arg(ARG_CANCEL_VOTE_THRESHOLD_BPS).unwrap_or(DEFAULT_CANCEL_VOTE_THRESHOLD_BPS);
+if cancel_vote_threshold_bps > BPS_DENOMINATOR {
+    runtime::revert(casper_types::ApiError::User(ERR_INVALID_BPS));
+}
+
+let mut named_keys = NamedKeys::new();
+named_keys.insert(ADMIN_KEY.to_string(), storage::new_uref(admin).into());
+named_keys.insert(TOTAL_REFUNDED_KEY.to_string(), storage::new_uref(0u64).into());
+named_keys.insert(TOTAL_SETTLED_KEY.to_string(), storage::new_uref(0u64).into());
+named_keys.insert(SETTLEMENT_DELAY_KEY.to_string(), storage::new_uref(settlement_delay).into());
+named_keys.insert(MAX_ESCROWS_PER_CREATOR_KEY.to_string(), storage::new_uref(max_escrows_per_creator).into());
+named_keys.insert(EVENTS_ENABLED_KEY.to_string(), storage::new_uref(events_enabled).into());
+named_keys.insert(LEAVE_FEE_BPS_KEY.to_string(), storage::new_uref(leave_fee_bps).into());
+named_keys.insert(LATE_LEAVE_FEE_BPS_KEY.to_string(), storage::new_uref(late_leave_fee_bps).into());
+named_keys.insert(FEE_RECEIVER_KEY.to_string(), storage::new_uref(fee_receiver).into());
+named_keys.insert(POST_COMPLETE_GRACE_KEY.to_string(), storage::new_uref(post_complete_grace).into());
+named_keys.insert(CREATION_DEPOSIT_KEY.to_string(), storage::new_uref(creation_deposit).into());
+named_keys.insert(DEPOSIT_TIMEOUT_KEY.to_string(), storage::new_uref(deposit_timeout).into());
+named_keys.insert(CANCEL_VOTE_THRESHOLD_BPS_KEY.to_string(), storage::new_uref(cancel_vote_threshold_bps).into());
+named_keys.insert(DEFAULT_DEADLINE_OFFSET_KEY.to_string(), storage::new_uref(default_deadline_offset).into());
+named_keys.insert(NUDGE_INTERVAL_KEY.to_string(), storage::new_uref(nudge_interval).into());
+if let Some(dust_recipient) = runtime::try_get_named_arg::<AccountHash>(ARG_DUST_RECIPIENT) {
+    named_keys.insert(DUST_RECIPIENT_KEY.to_string(), storage::new_uref(dust_recipient).into());
+}
+if let Some(verifier) = runtime::try_get_named_arg::<ContractHash>(ARG_VERIFIER_CONTRACT) {
+    named_keys.insert(VERIFIER_KEY.to_string(), storage::new_uref(verifier).into());
+}
+if let Some(price_oracle) = runtime::try_get_named_arg::<ContractHash>(ARG_PRICE_ORACLE) {
+    named_keys.insert(PRICE_ORACLE_KEY.to_string(), storage::new_uref(price_oracle).into());
+}
+named_keys.insert(ANYONE_CAN_CLONE_KEY.to_string(), storage::new_uref(anyone_can_clone).into());
+named_keys.insert(CREATOR_CAN_PURGE_KEY.to_string(), storage::new_uref(creator_can_purge).into());
+named_keys.insert(CREATION_RATE_LIMIT_KEY.to_string(), storage::new_uref(creation_rate_limit).into());
+named_keys.insert(IDLE_CANCEL_WINDOW_KEY.to_string(), storage::new_uref(idle_cancel_window).into());
+named_keys.insert(IDLE_CANCEL_BOUNTY_KEY.to_string(), storage::new_uref(idle_cancel_bounty).into());
+named_keys.insert(
+    MIN_SETTLE_DELAY_AFTER_LAST_JOIN_KEY.to_string(),
+    storage::new_uref(min_settle_delay_after_last_join).into(),
+);
+named_keys.insert(CREATION_RATE_WINDOW_KEY.to_string(), storage::new_uref(creation_rate_window).into());
+named_keys.insert(INSTALLED_AT_KEY.to_string(), storage::new_uref(now_millis()).into());
+named_keys.insert(ABI_KEY.to_string(), storage::new_uref(describe_entry_points(&entry_points)).into());
+// The version number and hash are only known once the contract exists, so
+// these start empty and are filled in below.
+let versions_uref = storage::new_uref(Vec::<u32>::new());
+named_keys.insert(VERSIONS_KEY.to_string(), versions_uref.into());
+let self_hash_uref = storage::new_uref(ContractHash::default());
+named_keys.insert(SELF_HASH_KEY.to_string(), self_hash_uref.into());
+
+// Lets one account hold several independent deployments side by side.
+let name_prefix: String = runtime::try_get_named_arg(ARG_NAME_PREFIX).unwrap_or_default();
 
 let (contract_hash, contract_version) = storage::new_contract(
     entry_points,
     Some(named_keys),
-    Some(CONTRACT_PACKAGE_KEY.to_string()),
-    Some(CONTRACT_ACCESS_KEY.to_string()),
+    Some(prefixed_key(&name_prefix, CONTRACT_PACKAGE_KEY)),
+    Some(prefixed_key(&name_prefix, CONTRACT_ACCESS_KEY)),
     None,
 );
 
-runtime::put_key(CONTRACT_KEY, contract_hash.into());
+runtime::put_key(&prefixed_key(&name_prefix, CONTRACT_KEY), contract_hash.into());
+storage::write(self_hash_uref, contract_hash);
 
 let version_uref = storage::new_uref(contract_version);
-runtime::put_key(CONTRACT_VERSION_KEY, version_uref.into());
+runtime::put_key(&prefixed_key(&name_prefix, CONTRACT_VERSION_KEY), version_uref.into());
+
+let mut versions: Vec<u32> = storage::read(versions_uref)
+    .unwrap_or_revert()
+    .unwrap_or_revert();
+versions.push(contract_version);
+storage::write(versions_uref, versions);
 
 let main_purse = system::create_purse();
-runtime::put_key(CONTRACT_PURSE_KEY, main_purse.into());
+runtime::put_key(&prefixed_key(&name_prefix, CONTRACT_PURSE_KEY), main_purse.into());
 }
\ No newline at end of file
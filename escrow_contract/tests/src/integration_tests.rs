@@ -4,14 +4,21 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{cell::Cell, collections::BTreeMap, path::PathBuf};
 
     use casper_engine_test_support::{
-        DeployItemBuilder, ExecuteRequestBuilder, LmdbWasmTestBuilder, ARG_AMOUNT,
-        DEFAULT_ACCOUNT_ADDR, DEFAULT_PAYMENT, LOCAL_GENESIS_REQUEST,
+        DeployItemBuilder, ExecuteRequestBuilder, LmdbWasmTestBuilder, TransferRequestBuilder,
+        ARG_AMOUNT, DEFAULT_ACCOUNT_ADDR, DEFAULT_PAYMENT, LOCAL_GENESIS_REQUEST,
+        MINIMUM_ACCOUNT_CREATION_BALANCE,
     };
     use casper_execution_engine::{engine_state::Error, execution::ExecError};
-    use casper_types::{runtime_args, ApiError, Key, RuntimeArgs};
+    use casper_types::{
+        account::AccountHash,
+        bytesrepr::{Bytes, FromBytes},
+        contracts::ContractHash,
+        runtime_args, AddressableEntityHash, ApiError, CLTyped, CLValue, Key, RuntimeArgs,
+        StoredValue, URef,
+    };
 
     // Define `KEY` constant to match that in the contract.
     const KEY: &str = "my-key-name";
@@ -19,6 +26,383 @@ mod tests {
     const RUNTIME_ARG_NAME: &str = "message";
     const CONTRACT_WASM: &str = "contract.wasm";
 
+    const SESSION_WASM: &str = "escrow_session.wasm";
+
+    const CONTRACT_KEY: &str = "group_escrow_contract";
+    const ARG_ESCROW_ID: &str = "escrow_id";
+    const ARG_TARGET_AMOUNT: &str = "target_amount";
+    const ARG_DEADLINE: &str = "deadline";
+    const ARG_PARTICIPANT: &str = "participant";
+    const ARG_NEW_ADMIN: &str = "new_admin";
+    const ARG_SETTLEMENT_DELAY: &str = "settlement_delay";
+    const ARG_CREATOR: &str = "creator";
+    const ARG_EXCLUDE_CREATOR: &str = "exclude_creator";
+    const ARG_EVENTS_ENABLED: &str = "events_enabled";
+    const ARG_MEMO: &str = "memo";
+    const ARG_NEW_MEMO: &str = "new_memo";
+    const ARG_ESCROWS: &str = "escrows";
+    const ARG_COSIGNER: &str = "cosigner";
+    const ARG_LEAVE_FEE_BPS: &str = "leave_fee_bps";
+    const ARG_FEE_RECEIVER: &str = "fee_receiver";
+    const ARG_TEMPLATE_NAME: &str = "template_name";
+    const ARG_NUM_PARTICIPANTS: &str = "num_participants";
+    const ARG_DEADLINE_OFFSET: &str = "deadline_offset";
+    const ARG_GATING_NFT: &str = "gating_nft";
+    const ARG_SETTLE_TO_CREATOR: &str = "settle_to_creator";
+    const ARG_GRACE_REFUND: &str = "grace_refund";
+    const ARG_POST_COMPLETE_GRACE: &str = "post_complete_grace";
+    const ARG_RECIPIENTS: &str = "recipients";
+    const ARG_IDEMPOTENCY_KEY: &str = "idempotency_key";
+    const ARG_STATUS: &str = "status";
+    const ARG_OFFSET: &str = "offset";
+    const ARG_LIMIT: &str = "limit";
+    const ARG_MIN_STAKE_DURATION: &str = "min_stake_duration";
+    const ARG_CREATION_DEPOSIT: &str = "creation_deposit";
+    const ARG_DEPOSIT_TIMEOUT: &str = "deposit_timeout";
+    const ARG_DEFAULT_DEADLINE_OFFSET: &str = "default_deadline_offset";
+    const ARG_NUDGE_INTERVAL: &str = "nudge_interval";
+    const ARG_DUST_RECIPIENT: &str = "dust_recipient";
+    const ARG_ORACLE: &str = "oracle";
+    const ARG_CONDITION_KEY: &str = "condition_key";
+    const ARG_KEEP_EXCESS: &str = "keep_excess";
+    const ARG_SOURCE_ESCROW_ID: &str = "source_escrow_id";
+    const ARG_MAX_COUNT: &str = "max_count";
+    const ARG_ENCRYPTED_MEMO: &str = "encrypted_memo";
+    const ARG_NAME_PREFIX: &str = "name_prefix";
+    const ARG_NEW_REFUND: &str = "new_refund";
+    const ARG_GAS_REBATE: &str = "gas_rebate";
+    const ARG_ACCOUNT: &str = "account";
+    const ARG_MIN_CONTRIBUTION: &str = "min_contribution";
+    const ARG_KEY: &str = "key";
+    const ARG_VALUE: &str = "value";
+    const ARG_SETTLEMENT_PURSE: &str = "settlement_purse";
+    const ARG_CREATION_RATE_LIMIT: &str = "creation_rate_limit";
+    const ARG_CREATION_RATE_WINDOW: &str = "creation_rate_window";
+    const ARG_IDLE_CANCEL_WINDOW: &str = "idle_cancel_window";
+    const ARG_IDLE_CANCEL_BOUNTY: &str = "idle_cancel_bounty";
+    const ARG_SETTLE_PARTIAL_AT_DEADLINE: &str = "settle_partial_at_deadline";
+    const ARG_ESCROW_IDS: &str = "escrow_ids";
+    const ARG_LATE_LEAVE_FEE_BPS: &str = "late_leave_fee_bps";
+    const ARG_ESCROW_ADMIN: &str = "escrow_admin";
+    const ARG_CANCEL: &str = "cancel";
+    const ARG_VERIFIER_CONTRACT: &str = "verifier_contract";
+    const ARG_TAGS: &str = "tags";
+    const ARG_TAG: &str = "tag";
+    const ARG_MAX_ACCUMULATED: &str = "max_accumulated";
+    const ARG_SLOT_INDEX: &str = "slot_index";
+    const ARG_MIN_SETTLE_DELAY_AFTER_LAST_JOIN: &str = "min_settle_delay_after_last_join";
+    const ARG_DISPLAY_NAME: &str = "display_name";
+    const ARG_PRICE_ORACLE: &str = "price_oracle";
+    const ARG_BENEFICIARIES: &str = "beneficiaries";
+    const ARG_CHARITY: &str = "charity";
+    const ARG_CHARITY_BPS: &str = "charity_bps";
+    const ARG_CANCEL_VOTE_THRESHOLD_BPS: &str = "cancel_vote_threshold_bps";
+    const ARG_CONTRACT_HASH: &str = "contract_hash";
+    const ARG_ENTRY_POINT: &str = "entry_point";
+    const ARG_FUNDING: &str = "funding";
+
+    thread_local! {
+        static BLOCK_TIME: Cell<u64> = const { Cell::new(0) };
+    }
+
+    /// Sets the block time (milliseconds) used for subsequent requests in this test.
+    fn set_block_time(block_time: u64) {
+        BLOCK_TIME.with(|cell| cell.set(block_time));
+    }
+
+    fn block_time() -> u64 {
+        BLOCK_TIME.with(Cell::get)
+    }
+
+    fn install_contract() -> LmdbWasmTestBuilder {
+        install_contract_with_args(RuntimeArgs::new())
+    }
+
+    fn install_contract_with_args(args: RuntimeArgs) -> LmdbWasmTestBuilder {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let install_request =
+            ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, CONTRACT_WASM, args)
+                .with_block_time(block_time())
+                .build();
+        builder.exec(install_request).commit().expect_success();
+
+        builder
+    }
+
+    fn assert_install_user_error(args: RuntimeArgs, code: u16) {
+        let mut builder = LmdbWasmTestBuilder::default();
+        builder.run_genesis(LOCAL_GENESIS_REQUEST.clone()).commit();
+
+        let install_request =
+            ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, CONTRACT_WASM, args)
+                .with_block_time(block_time())
+                .build();
+        builder.exec(install_request).commit().expect_failure();
+
+        let actual_error = builder.get_error().expect("must have error");
+        assert!(
+            matches!(
+                actual_error,
+                Error::Exec(ExecError::Revert(ApiError::User(actual))) if actual == code
+            ),
+            "Expected user error {}, received {:?}",
+            code,
+            actual_error
+        );
+    }
+
+    fn create_escrow(builder: &mut LmdbWasmTestBuilder, escrow_id: u64, amount: u64, target: u64) {
+        call_contract(
+            builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => escrow_id,
+                ARG_AMOUNT => amount,
+                ARG_TARGET_AMOUNT => target,
+            },
+        );
+    }
+
+    fn create_cosigned_escrow(builder: &mut LmdbWasmTestBuilder, cosigner: AccountHash) {
+        call_contract(
+            builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_COSIGNER => cosigner,
+            },
+        );
+    }
+
+    fn complete_grace_refund_escrow(builder: &mut LmdbWasmTestBuilder, creator: AccountHash) {
+        call_contract_as(
+            builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_SETTLE_TO_CREATOR => true,
+                ARG_GRACE_REFUND => true,
+            },
+        );
+        call_with_purse(
+            builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_contract(
+            builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+    }
+
+    /// Creates escrow 1 through the session wasm, which funds the creation deposit.
+    fn create_escrow_with_deposit(builder: &mut LmdbWasmTestBuilder, deposit: u64) {
+        call_with_purse(
+            builder,
+            "create_escrow",
+            deposit,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+            },
+        );
+    }
+
+    fn create_funded_account(builder: &mut LmdbWasmTestBuilder, seed: u8) -> AccountHash {
+        let account = AccountHash::new([seed; 32]);
+        let transfer_request =
+            TransferRequestBuilder::new(MINIMUM_ACCOUNT_CREATION_BALANCE * 10, account).build();
+        builder
+            .transfer_and_commit(transfer_request)
+            .expect_success();
+        account
+    }
+
+    fn contract_hash(builder: &LmdbWasmTestBuilder) -> AddressableEntityHash {
+        builder
+            .get_named_keys_by_account_hash(*DEFAULT_ACCOUNT_ADDR)
+            .get(CONTRACT_KEY)
+            .and_then(|key| key.into_hash_addr())
+            .map(AddressableEntityHash::new)
+            .expect("should have contract hash")
+    }
+
+    fn read_contract_named_value<T: CLTyped + FromBytes>(
+        builder: &LmdbWasmTestBuilder,
+        name: &str,
+    ) -> T {
+        let contract_key = Key::Hash(contract_hash(builder).value());
+        builder
+            .query(None, contract_key, &[name.to_string()])
+            .expect("should have named key")
+            .as_cl_value()
+            .expect("should be cl value")
+            .clone()
+            .into_t()
+            .expect("should have expected type")
+    }
+
+    fn call_contract(builder: &mut LmdbWasmTestBuilder, entry_point: &str, args: RuntimeArgs) {
+        call_contract_as(builder, *DEFAULT_ACCOUNT_ADDR, entry_point, args);
+    }
+
+    fn call_contract_as(
+        builder: &mut LmdbWasmTestBuilder,
+        sender: AccountHash,
+        entry_point: &str,
+        args: RuntimeArgs,
+    ) {
+        let contract_hash = contract_hash(builder);
+        let request =
+            ExecuteRequestBuilder::contract_call_by_hash(sender, contract_hash, entry_point, args)
+                .with_block_time(block_time())
+                .build();
+        builder.exec(request).commit().expect_success();
+    }
+
+    fn assert_user_error(
+        builder: &mut LmdbWasmTestBuilder,
+        sender: AccountHash,
+        entry_point: &str,
+        args: RuntimeArgs,
+        code: u16,
+    ) {
+        let contract_hash = contract_hash(builder);
+        let request =
+            ExecuteRequestBuilder::contract_call_by_hash(sender, contract_hash, entry_point, args)
+                .with_block_time(block_time())
+                .build();
+        builder.exec(request).commit().expect_failure();
+
+        let actual_error = builder.get_error().expect("must have error");
+        assert!(
+            matches!(
+                actual_error,
+                Error::Exec(ExecError::Revert(ApiError::User(actual))) if actual == code
+            ),
+            "Expected user error {}, received {:?}",
+            code,
+            actual_error
+        );
+    }
+
+    /// Like `assert_user_error`, for a purse-taking entry point called through
+    /// the session wasm with `funding` motes.
+    fn assert_session_user_error(
+        builder: &mut LmdbWasmTestBuilder,
+        sender: AccountHash,
+        entry_point: &str,
+        funding: u64,
+        mut args: RuntimeArgs,
+        code: u16,
+    ) {
+        let contract_hash = ContractHash::new(contract_hash(builder).value());
+        args.insert(ARG_CONTRACT_HASH, contract_hash)
+            .expect("should insert contract hash");
+        args.insert(ARG_ENTRY_POINT, entry_point.to_string())
+            .expect("should insert entry point");
+        args.insert(ARG_FUNDING, funding)
+            .expect("should insert funding");
+        let request = ExecuteRequestBuilder::standard(sender, SESSION_WASM, args)
+            .with_block_time(block_time())
+            .build();
+        builder.exec(request).commit().expect_failure();
+
+        let actual_error = builder.get_error().expect("must have error");
+        assert!(
+            matches!(
+                actual_error,
+                Error::Exec(ExecError::Revert(ApiError::User(actual))) if actual == code
+            ),
+            "Expected user error {}, received {:?}",
+            code,
+            actual_error
+        );
+    }
+
+    /// Calls a purse-taking entry point through the session wasm, which funds a
+    /// temporary purse with `funding` motes and sweeps any leftover back.
+    fn call_with_purse(
+        builder: &mut LmdbWasmTestBuilder,
+        entry_point: &str,
+        funding: u64,
+        args: RuntimeArgs,
+    ) {
+        call_with_purse_as(builder, *DEFAULT_ACCOUNT_ADDR, entry_point, funding, args);
+    }
+
+    fn call_with_purse_as(
+        builder: &mut LmdbWasmTestBuilder,
+        sender: AccountHash,
+        entry_point: &str,
+        funding: u64,
+        mut args: RuntimeArgs,
+    ) {
+        let contract_hash = ContractHash::new(contract_hash(builder).value());
+        args.insert(ARG_CONTRACT_HASH, contract_hash)
+            .expect("should insert contract hash");
+        args.insert(ARG_ENTRY_POINT, entry_point.to_string())
+            .expect("should insert entry point");
+        args.insert(ARG_FUNDING, funding)
+            .expect("should insert funding");
+        let request = ExecuteRequestBuilder::standard(sender, SESSION_WASM, args)
+            .with_block_time(block_time())
+            .build();
+        builder.exec(request).commit().expect_success();
+    }
+
+    fn query_contract<T: CLTyped + FromBytes>(
+        builder: &mut LmdbWasmTestBuilder,
+        entry_point: &str,
+        args: RuntimeArgs,
+    ) -> T {
+        call_contract(builder, entry_point, args);
+        builder
+            .get_last_exec_result()
+            .expect("should have exec result")
+            .ret()
+            .expect("should return a value")
+            .clone()
+            .into_t()
+            .expect("should have expected return type")
+    }
+
+    fn escrows_by_status(
+        builder: &mut LmdbWasmTestBuilder,
+        status: u8,
+        offset: u64,
+        limit: u64,
+    ) -> Vec<u64> {
+        query_contract(
+            builder,
+            "get_escrows_by_status",
+            runtime_args! { ARG_STATUS => status, ARG_OFFSET => offset, ARG_LIMIT => limit },
+        )
+    }
+
+    fn escrows_by_tag(
+        builder: &mut LmdbWasmTestBuilder,
+        tag: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Vec<u64> {
+        query_contract(
+            builder,
+            "get_escrows_by_tag",
+            runtime_args! { ARG_TAG => tag.to_string(), ARG_OFFSET => offset, ARG_LIMIT => limit },
+        )
+    }
+
     #[test]
     fn should_store_hello_world() {
         let mut builder = LmdbWasmTestBuilder::default();
@@ -100,4 +484,4553 @@ mod tests {
             actual_error
         );
     }
+
+    #[test]
+    fn should_store_and_read_escrow_deadline() {
+        let mut builder = install_contract();
+
+        // Deadlines are milliseconds, the same unit as the block time.
+        let deadline: u64 = 1_700_000_000_000;
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_DEADLINE => deadline,
+            },
+        );
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 2u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+            },
+        );
+
+        let stored: u64 = query_contract(
+            &mut builder,
+            "get_deadline",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(stored, deadline);
+
+        let no_deadline: u64 = query_contract(
+            &mut builder,
+            "get_deadline",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(no_deadline, 0);
+    }
+
+    #[test]
+    fn should_credit_participant_once_installments_reach_split() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 300);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            40,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 40u64 },
+        );
+
+        let paid: u64 = query_contract(
+            &mut builder,
+            "get_participant_paid",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_eq!(paid, 40);
+        let participants: Vec<AccountHash> = query_contract(
+            &mut builder,
+            "get_participants",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert!(participants.is_empty());
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            60,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 60u64 },
+        );
+
+        let paid: u64 = query_contract(
+            &mut builder,
+            "get_participant_paid",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_eq!(paid, 100);
+        let participants: Vec<AccountHash> = query_contract(
+            &mut builder,
+            "get_participants",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(participants, vec![*DEFAULT_ACCOUNT_ADDR]);
+        let stake: u64 = query_contract(
+            &mut builder,
+            "get_stake",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_eq!(stake, 100);
+    }
+
+    #[test]
+    fn should_refund_partial_installments() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 300);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            40,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 40u64 },
+        );
+        call_with_purse(
+            &mut builder,
+            "refund",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+
+        let paid: u64 = query_contract(
+            &mut builder,
+            "get_participant_paid",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_eq!(paid, 0);
+        let balance: u64 = query_contract(
+            &mut builder,
+            "get_escrow_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(balance, 0);
+    }
+
+    #[test]
+    fn should_default_admin_to_installer_and_transfer_it() {
+        let mut builder = install_contract();
+        let new_admin = create_funded_account(&mut builder, 2);
+
+        let admin: AccountHash = query_contract(&mut builder, "get_admin", RuntimeArgs::new());
+        assert_eq!(admin, *DEFAULT_ACCOUNT_ADDR);
+
+        assert_user_error(
+            &mut builder,
+            new_admin,
+            "transfer_admin",
+            runtime_args! { ARG_NEW_ADMIN => new_admin },
+            107,
+        );
+
+        call_contract(
+            &mut builder,
+            "transfer_admin",
+            runtime_args! { ARG_NEW_ADMIN => new_admin },
+        );
+        let admin: AccountHash = query_contract(&mut builder, "get_admin", RuntimeArgs::new());
+        assert_eq!(admin, new_admin);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "transfer_admin",
+            runtime_args! { ARG_NEW_ADMIN => *DEFAULT_ACCOUNT_ADDR },
+            107,
+        );
+    }
+
+    #[test]
+    fn should_track_total_refunded_across_escrows() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 300);
+        create_escrow(&mut builder, 2, 100, 300);
+
+        for (escrow_id, amount) in [(1u64, 40u64), (2, 25)] {
+            call_with_purse(
+                &mut builder,
+                "pay_installment",
+                amount,
+                runtime_args! { ARG_ESCROW_ID => escrow_id, ARG_AMOUNT => amount },
+            );
+            call_with_purse(
+                &mut builder,
+                "refund",
+                0,
+                runtime_args! {
+                    ARG_ESCROW_ID => escrow_id,
+                    ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR,
+                },
+            );
+        }
+
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 65);
+        let total_settled: u64 = read_contract_named_value(&builder, "total_settled");
+        assert_eq!(total_settled, 0);
+    }
+
+    #[test]
+    fn should_only_finalize_settlement_after_delay() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_SETTLEMENT_DELAY => 1_000u64,
+        });
+        create_escrow(&mut builder, 1, 100, 100);
+
+        set_block_time(10_000);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        let settle_after: u64 = query_contract(
+            &mut builder,
+            "get_settle_after",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(settle_after, 11_000);
+
+        set_block_time(10_500);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "finalize_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            109,
+        );
+
+        set_block_time(11_000);
+        call_contract(
+            &mut builder,
+            "finalize_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        let completed: bool = read_contract_named_value(&builder, "escrow_1_completed");
+        assert!(completed);
+    }
+
+    #[test]
+    fn should_block_settlement_when_disputed_during_delay() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_SETTLEMENT_DELAY => 1_000u64,
+        });
+        let freeloader = create_funded_account(&mut builder, 190);
+        create_escrow(&mut builder, 1, 100, 100);
+        call_contract_as(
+            &mut builder,
+            freeloader,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        set_block_time(10_000);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            freeloader,
+            "raise_dispute",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            111,
+        );
+        call_contract(
+            &mut builder,
+            "raise_dispute",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        set_block_time(20_000);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "finalize_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            112,
+        );
+    }
+
+    #[test]
+    fn should_sum_creator_exposure_over_open_escrows() {
+        let mut builder = install_contract();
+        let other_creator = create_funded_account(&mut builder, 2);
+
+        let exposure: u64 = query_contract(
+            &mut builder,
+            "get_creator_exposure",
+            runtime_args! { ARG_CREATOR => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_eq!(exposure, 0);
+
+        create_escrow(&mut builder, 1, 100, 300);
+        create_escrow(&mut builder, 2, 50, 300);
+        create_escrow(&mut builder, 3, 70, 70);
+        for (escrow_id, amount) in [(1u64, 100u64), (2, 50), (3, 70)] {
+            call_with_purse(
+                &mut builder,
+                "pay_installment",
+                amount,
+                runtime_args! { ARG_ESCROW_ID => escrow_id, ARG_AMOUNT => amount },
+            );
+        }
+        // Completed escrows no longer count towards exposure.
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 3u64 },
+        );
+
+        let exposure: u64 = query_contract(
+            &mut builder,
+            "get_creator_exposure",
+            runtime_args! { ARG_CREATOR => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_eq!(exposure, 150);
+
+        let other_exposure: u64 = query_contract(
+            &mut builder,
+            "get_creator_exposure",
+            runtime_args! { ARG_CREATOR => other_creator },
+        );
+        assert_eq!(other_exposure, 0);
+    }
+
+    #[test]
+    fn should_reject_zero_amount_escrow() {
+        let mut builder = install_contract();
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 0u64,
+                ARG_TARGET_AMOUNT => 300u64,
+            },
+            114,
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 0u64,
+            },
+            114,
+        );
+    }
+
+    #[test]
+    fn should_return_uniform_split_schedule() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 250);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+
+        let schedule: Vec<(u64, bool)> = query_contract(
+            &mut builder,
+            "get_split_schedule",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(schedule, vec![(100, true), (100, false), (50, false)]);
+    }
+
+    #[test]
+    fn should_reject_creator_joining_when_excluded() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 2);
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_EXCLUDE_CREATOR => true,
+            },
+        );
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            115,
+        );
+
+        call_contract_as(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        let participants: Vec<AccountHash> = query_contract(
+            &mut builder,
+            "get_participants",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(participants, vec![friend]);
+    }
+
+    #[test]
+    fn should_skip_event_writes_when_events_disabled() {
+        let mut builder = install_contract_with_args(runtime_args! { ARG_EVENTS_ENABLED => false });
+
+        let events_enabled: bool =
+            query_contract(&mut builder, "get_events_enabled", RuntimeArgs::new());
+        assert!(!events_enabled);
+
+        create_escrow(&mut builder, 1, 100, 100);
+        let contract_key = Key::Hash(contract_hash(&builder).value());
+        assert!(builder
+            .query(None, contract_key, &["event_escrow_created_1".to_string()])
+            .is_err());
+
+        let mut builder = install_contract();
+        let events_enabled: bool =
+            query_contract(&mut builder, "get_events_enabled", RuntimeArgs::new());
+        assert!(events_enabled);
+
+        create_escrow(&mut builder, 1, 100, 100);
+        let event: String = read_contract_named_value(&builder, "event_escrow_created_1");
+        assert_eq!(event, "amount:100,target:100,deadline:0");
+    }
+
+    #[test]
+    fn should_let_only_creator_update_memo() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 2);
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_MEMO => "Lisbon trpi".to_string(),
+            },
+        );
+
+        call_contract(
+            &mut builder,
+            "update_memo",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_NEW_MEMO => "Lisbon trip".to_string() },
+        );
+        let memo: String = read_contract_named_value(&builder, "escrow_1_memo");
+        assert_eq!(memo, "Lisbon trip");
+
+        assert_user_error(
+            &mut builder,
+            friend,
+            "update_memo",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_NEW_MEMO => "Porto trip".to_string() },
+            117,
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "update_memo",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_NEW_MEMO => "x".repeat(257) },
+            116,
+        );
+        let memo: String = read_contract_named_value(&builder, "escrow_1_memo");
+        assert_eq!(memo, "Lisbon trip");
+    }
+
+    #[test]
+    fn should_create_escrow_batch() {
+        let mut builder = install_contract();
+
+        let messages: Vec<String> = query_contract(
+            &mut builder,
+            "create_escrow_batch",
+            runtime_args! {
+                ARG_ESCROWS => vec![(1u64, 100u64, 400u64), (2u64, 50u64, 200u64), (3u64, 25u64, 100u64)],
+            },
+        );
+        assert_eq!(
+            messages,
+            vec![
+                "Escrow 1 created with target 400".to_string(),
+                "Escrow 2 created with target 200".to_string(),
+                "Escrow 3 created with target 100".to_string(),
+            ]
+        );
+
+        for (escrow_id, target) in [(1u64, 400u64), (2, 200), (3, 100)] {
+            let stored: u64 =
+                read_contract_named_value(&builder, &format!("escrow_{}_target", escrow_id));
+            assert_eq!(stored, target);
+        }
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_escrow_batch",
+            runtime_args! { ARG_ESCROWS => vec![(4u64, 10u64, 10u64); 11] },
+            118,
+        );
+    }
+
+    #[test]
+    fn should_settle_cosigned_escrow_only_after_both_approve() {
+        let mut builder = install_contract();
+        let cosigner = create_funded_account(&mut builder, 2);
+        create_cosigned_escrow(&mut builder, cosigner);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+
+        call_contract(
+            &mut builder,
+            "approve_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            119,
+        );
+
+        call_contract_as(
+            &mut builder,
+            cosigner,
+            "approve_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        let completed: bool = read_contract_named_value(&builder, "escrow_1_completed");
+        assert!(completed);
+    }
+
+    #[test]
+    fn should_refund_everyone_when_cosigner_vetoes() {
+        let mut builder = install_contract();
+        let cosigner = create_funded_account(&mut builder, 2);
+        let friend = create_funded_account(&mut builder, 3);
+        let outsider = create_funded_account(&mut builder, 4);
+        create_cosigned_escrow(&mut builder, cosigner);
+
+        call_with_purse_as(
+            &mut builder,
+            outsider,
+            "pay_installment",
+            40,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 40u64 },
+        );
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+
+        assert_user_error(
+            &mut builder,
+            outsider,
+            "veto_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            120,
+        );
+
+        let friend_purse = builder
+            .get_account(friend)
+            .expect("should have account")
+            .main_purse();
+        let balance_before = builder.get_purse_balance(friend_purse);
+        call_contract_as(
+            &mut builder,
+            cosigner,
+            "veto_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(
+            builder.get_purse_balance(friend_purse),
+            balance_before + 100
+        );
+
+        let balance: u64 = query_contract(
+            &mut builder,
+            "get_escrow_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(balance, 0);
+
+        let status: u8 = read_contract_named_value(&builder, "escrow_1_status");
+        assert_eq!(status, 4);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            119,
+        );
+    }
+
+    #[test]
+    fn should_deduct_leave_fee_on_refund() {
+        assert_install_user_error(runtime_args! { ARG_LEAVE_FEE_BPS => 10_001u64 }, 182);
+        assert_install_user_error(runtime_args! { ARG_LATE_LEAVE_FEE_BPS => 10_001u64 }, 182);
+
+        let fee_receiver = AccountHash::new([9; 32]);
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_LEAVE_FEE_BPS => 1_000u64,
+            ARG_FEE_RECEIVER => fee_receiver,
+        });
+        create_funded_account(&mut builder, 9);
+        let leave_fee: u64 = query_contract(&mut builder, "get_leave_fee", RuntimeArgs::new());
+        assert_eq!(leave_fee, 1_000);
+
+        create_escrow(&mut builder, 1, 100, 300);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        assert_session_user_error(
+            &mut builder,
+            fee_receiver,
+            "refund",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+            111,
+        );
+
+        let receiver_purse = builder
+            .get_account(fee_receiver)
+            .expect("should have account")
+            .main_purse();
+        let receiver_before = builder.get_purse_balance(receiver_purse);
+        call_with_purse(
+            &mut builder,
+            "refund",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+
+        assert_eq!(
+            builder.get_purse_balance(receiver_purse),
+            receiver_before + 10
+        );
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 90);
+        let balance: u64 = query_contract(
+            &mut builder,
+            "get_escrow_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(balance, 0);
+    }
+
+    #[test]
+    fn should_count_only_fully_paid_participants() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 2);
+        create_escrow(&mut builder, 1, 100, 300);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "pay_installment",
+            40,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 40u64 },
+        );
+
+        let count: u64 = query_contract(
+            &mut builder,
+            "get_participant_count",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn should_create_escrow_from_saved_template() {
+        let mut builder = install_contract();
+        call_contract(
+            &mut builder,
+            "save_template",
+            runtime_args! {
+                ARG_TEMPLATE_NAME => "weekly_dinner".to_string(),
+                ARG_NUM_PARTICIPANTS => 3u64,
+                ARG_DEADLINE_OFFSET => 1_000u64,
+            },
+        );
+        let template: (u64, u64, bool) = query_contract(
+            &mut builder,
+            "get_template",
+            runtime_args! {
+                ARG_CREATOR => *DEFAULT_ACCOUNT_ADDR,
+                ARG_TEMPLATE_NAME => "weekly_dinner".to_string(),
+            },
+        );
+        assert_eq!(template, (3, 1_000, false));
+
+        set_block_time(5_000);
+        call_contract(
+            &mut builder,
+            "create_from_template",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_TEMPLATE_NAME => "weekly_dinner".to_string(),
+                ARG_TARGET_AMOUNT => 250u64,
+            },
+        );
+        let split: u64 = read_contract_named_value(&builder, "escrow_1");
+        assert_eq!(split, 84);
+        let deadline: u64 = read_contract_named_value(&builder, "escrow_1_deadline");
+        assert_eq!(deadline, 6_000);
+
+        call_contract(
+            &mut builder,
+            "save_template",
+            runtime_args! {
+                ARG_TEMPLATE_NAME => "four_way".to_string(),
+                ARG_NUM_PARTICIPANTS => 4u64,
+            },
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_from_template",
+            runtime_args! {
+                ARG_ESCROW_ID => 2u64,
+                ARG_TEMPLATE_NAME => "four_way".to_string(),
+                ARG_TARGET_AMOUNT => 9u64,
+            },
+            183,
+        );
+        call_contract(
+            &mut builder,
+            "create_from_template",
+            runtime_args! {
+                ARG_ESCROW_ID => 2u64,
+                ARG_TEMPLATE_NAME => "four_way".to_string(),
+                ARG_TARGET_AMOUNT => 10u64,
+            },
+        );
+        let schedule: Vec<(u64, bool)> = query_contract(
+            &mut builder,
+            "get_split_schedule",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(
+            schedule,
+            vec![(3, false), (3, false), (3, false), (1, false)]
+        );
+
+        call_contract(
+            &mut builder,
+            "delete_template",
+            runtime_args! { ARG_TEMPLATE_NAME => "weekly_dinner".to_string() },
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_from_template",
+            runtime_args! {
+                ARG_ESCROW_ID => 3u64,
+                ARG_TEMPLATE_NAME => "weekly_dinner".to_string(),
+                ARG_TARGET_AMOUNT => 250u64,
+            },
+            123,
+        );
+    }
+
+    #[test]
+    fn should_reconcile_escrow_purse_with_ledger() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 2);
+        create_escrow(&mut builder, 1, 100, 300);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "pay_installment",
+            40,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 40u64 },
+        );
+
+        let (expected, actual): (u64, u64) = query_contract(
+            &mut builder,
+            "reconcile",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!((expected, actual), (140, 140));
+
+        assert_user_error(
+            &mut builder,
+            friend,
+            "reconcile",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            107,
+        );
+    }
+
+    #[test]
+    fn should_store_gating_nft_and_check_it_on_join() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 2);
+        create_escrow(&mut builder, 1, 100, 300);
+        // Any stored contract without `balance_of` stands in for a gating NFT here.
+        let gating_nft = ContractHash::new(contract_hash(&builder).value());
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 2u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_GATING_NFT => gating_nft,
+            },
+        );
+
+        let ungated: Option<ContractHash> = query_contract(
+            &mut builder,
+            "get_gating_nft",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(ungated, None);
+        let gated: Option<ContractHash> = query_contract(
+            &mut builder,
+            "get_gating_nft",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(gated, Some(gating_nft));
+
+        call_contract_as(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        let request = ExecuteRequestBuilder::contract_call_by_hash(
+            friend,
+            contract_hash(&builder),
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        )
+        .build();
+        builder.exec(request).commit().expect_failure();
+    }
+
+    #[test]
+    fn should_pay_pooled_funds_to_creator_on_settlement() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 2);
+        call_contract_as(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 200u64,
+                ARG_TARGET_AMOUNT => 200u64,
+                ARG_SETTLE_TO_CREATOR => true,
+            },
+        );
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            200,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 200u64 },
+        );
+
+        let creator_purse = builder
+            .get_account(creator)
+            .expect("should have account")
+            .main_purse();
+        let balance_before = builder.get_purse_balance(creator_purse);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(
+            builder.get_purse_balance(creator_purse),
+            balance_before + 200
+        );
+
+        let total_settled: u64 = read_contract_named_value(&builder, "total_settled");
+        assert_eq!(total_settled, 200);
+        let participant_purse = builder
+            .get_account(*DEFAULT_ACCOUNT_ADDR)
+            .expect("should have account")
+            .main_purse();
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "withdraw",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR,
+                "purse" => participant_purse,
+            },
+            125,
+        );
+    }
+
+    #[test]
+    fn should_pay_creator_only_after_post_complete_grace() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_POST_COMPLETE_GRACE => 1_000u64,
+        });
+        let creator = create_funded_account(&mut builder, 2);
+        set_block_time(10_000);
+        complete_grace_refund_escrow(&mut builder, creator);
+
+        let creator_purse = builder
+            .get_account(creator)
+            .expect("should have account")
+            .main_purse();
+        let balance_before = builder.get_purse_balance(creator_purse);
+
+        set_block_time(10_500);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "finalize_complete",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            128,
+        );
+
+        set_block_time(11_000);
+        call_contract(
+            &mut builder,
+            "finalize_complete",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(
+            builder.get_purse_balance(creator_purse),
+            balance_before + 100
+        );
+        assert_user_error(
+            &mut builder,
+            creator,
+            "refund_after_complete",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            127,
+        );
+    }
+
+    #[test]
+    fn should_let_creator_refund_everyone_during_grace() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_POST_COMPLETE_GRACE => 1_000u64,
+        });
+        let creator = create_funded_account(&mut builder, 2);
+        set_block_time(10_000);
+        complete_grace_refund_escrow(&mut builder, creator);
+
+        set_block_time(10_500);
+        call_contract_as(
+            &mut builder,
+            creator,
+            "refund_after_complete",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        let balance: u64 = query_contract(
+            &mut builder,
+            "get_escrow_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(balance, 0);
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 100);
+        let status: u8 = read_contract_named_value(&builder, "escrow_1_status");
+        assert_eq!(status, 4);
+
+        set_block_time(11_000);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "finalize_complete",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            127,
+        );
+    }
+
+    #[test]
+    fn should_report_whether_escrow_exists() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 300);
+
+        let exists: bool = query_contract(
+            &mut builder,
+            "escrow_exists",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert!(exists);
+        let exists: bool = query_contract(
+            &mut builder,
+            "escrow_exists",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert!(!exists);
+    }
+
+    #[test]
+    fn should_split_settlement_across_recipients() {
+        let mut builder = install_contract();
+        let caterer = create_funded_account(&mut builder, 2);
+        let florist = create_funded_account(&mut builder, 3);
+        let recipients = vec![(caterer, 200u64), (florist, 100u64)];
+        call_contract(
+            &mut builder,
+            "create_escrow_multi_recipient",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 300u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_RECIPIENTS => recipients.clone(),
+            },
+        );
+        let stored: Vec<(AccountHash, u64)> = query_contract(
+            &mut builder,
+            "get_recipients",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(stored, recipients);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            300,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 300u64 },
+        );
+        call_with_purse(
+            &mut builder,
+            "deposit_yield",
+            10,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 10u64 },
+        );
+
+        let caterer_purse = builder
+            .get_account(caterer)
+            .expect("should have account")
+            .main_purse();
+        let florist_purse = builder
+            .get_account(florist)
+            .expect("should have account")
+            .main_purse();
+        let caterer_before = builder.get_purse_balance(caterer_purse);
+        let florist_before = builder.get_purse_balance(florist_purse);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        // 10 * 200 / 300 rounds down to 6; the last recipient takes the dust.
+        assert_eq!(
+            builder.get_purse_balance(caterer_purse),
+            caterer_before + 206
+        );
+        assert_eq!(
+            builder.get_purse_balance(florist_purse),
+            florist_before + 104
+        );
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_escrow_multi_recipient",
+            runtime_args! {
+                ARG_ESCROW_ID => 2u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_RECIPIENTS => vec![(caterer, 200u64)],
+            },
+            130,
+        );
+    }
+
+    #[test]
+    fn should_ignore_retried_installment_with_same_idempotency_key() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 300);
+
+        for _ in 0..2 {
+            call_with_purse(
+                &mut builder,
+                "pay_installment",
+                40,
+                runtime_args! {
+                    ARG_ESCROW_ID => 1u64,
+                    ARG_AMOUNT => 40u64,
+                    ARG_IDEMPOTENCY_KEY => "4f1c9a".to_string(),
+                },
+            );
+        }
+        let paid: u64 = query_contract(
+            &mut builder,
+            "get_participant_paid",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_eq!(paid, 40);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            40,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 40u64,
+                ARG_IDEMPOTENCY_KEY => "b27e03".to_string(),
+            },
+        );
+        let balance: u64 = query_contract(
+            &mut builder,
+            "get_escrow_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(balance, 80);
+    }
+
+    #[test]
+    fn should_return_live_config() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_SETTLEMENT_DELAY => 1_000u64,
+            ARG_LEAVE_FEE_BPS => 250u64,
+        });
+        let new_admin = create_funded_account(&mut builder, 2);
+        call_contract(
+            &mut builder,
+            "transfer_admin",
+            runtime_args! { ARG_NEW_ADMIN => new_admin },
+        );
+
+        let config: BTreeMap<String, String> =
+            query_contract(&mut builder, "get_config", RuntimeArgs::new());
+        assert_eq!(config["admin"], new_admin.to_string());
+        assert_eq!(config["fee_receiver"], DEFAULT_ACCOUNT_ADDR.to_string());
+        assert_eq!(config["settlement_delay"], "1000");
+        assert_eq!(config["leave_fee_bps"], "250");
+        assert_eq!(config["max_escrows_per_creator"], "50");
+        assert_eq!(config["events_enabled"], "true");
+    }
+
+    #[test]
+    fn should_cap_installments_at_split() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 300);
+
+        for amount in [40u64, 40, 50] {
+            call_with_purse(
+                &mut builder,
+                "pay_installment",
+                amount,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => amount },
+            );
+        }
+
+        let paid: u64 = query_contract(
+            &mut builder,
+            "get_participant_paid",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_eq!(paid, 100);
+        // The 30 overshoot was never taken and is swept back to the payer.
+        let balance: u64 = query_contract(
+            &mut builder,
+            "get_escrow_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(balance, 100);
+
+        let account_purse = builder
+            .get_account(*DEFAULT_ACCOUNT_ADDR)
+            .expect("should have account")
+            .main_purse();
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "pay_installment",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 10u64,
+                "purse" => account_purse,
+            },
+            132,
+        );
+    }
+
+    #[test]
+    fn should_page_escrows_by_status() {
+        let mut builder = install_contract();
+        for escrow_id in 1..=3u64 {
+            create_escrow(&mut builder, escrow_id, 100, 100);
+        }
+        assert_eq!(escrows_by_status(&mut builder, 0, 0, 10), vec![1, 2, 3]);
+        assert_eq!(escrows_by_status(&mut builder, 0, 1, 1), vec![2]);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        assert_eq!(escrows_by_status(&mut builder, 0, 0, 10), vec![3, 2]);
+        assert_eq!(escrows_by_status(&mut builder, 1, 0, 10), Vec::<u64>::new());
+        assert_eq!(escrows_by_status(&mut builder, 2, 0, 10), vec![1]);
+    }
+
+    #[test]
+    fn should_lock_withdrawals_for_min_stake_duration() {
+        let mut builder = install_contract();
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_MIN_STAKE_DURATION => 1_000u64,
+            },
+        );
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        let unlock_time: u64 = query_contract(
+            &mut builder,
+            "get_unlock_time",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(unlock_time, 0);
+
+        set_block_time(10_000);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        let unlock_time: u64 = query_contract(
+            &mut builder,
+            "get_unlock_time",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(unlock_time, 11_000);
+
+        set_block_time(10_500);
+        let account_purse = builder
+            .get_account(*DEFAULT_ACCOUNT_ADDR)
+            .expect("should have account")
+            .main_purse();
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "withdraw",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR,
+                "purse" => account_purse,
+            },
+            133,
+        );
+
+        set_block_time(11_000);
+        call_with_purse(
+            &mut builder,
+            "withdraw",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        let balance: u64 = query_contract(
+            &mut builder,
+            "get_escrow_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(balance, 0);
+    }
+
+    #[test]
+    fn should_export_entry_point_schema() {
+        let mut builder = install_contract();
+
+        let abi: String = query_contract(&mut builder, "get_abi", RuntimeArgs::new());
+        let lines: Vec<&str> = abi.lines().collect();
+        assert!(lines.contains(&"get_abi() -> string"));
+        assert!(lines.contains(&"escrow_exists(escrow_id: u64) -> bool"));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("create_escrow(escrow_id: u64, ")));
+    }
+
+    #[test]
+    fn should_return_creation_deposit_once_terminal() {
+        let mut builder =
+            install_contract_with_args(runtime_args! { ARG_CREATION_DEPOSIT => 50u64 });
+        create_escrow_with_deposit(&mut builder, 50);
+
+        let deposit: u64 = query_contract(
+            &mut builder,
+            "get_creation_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(deposit, 50);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "claim_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            135,
+        );
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        call_contract(
+            &mut builder,
+            "claim_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        let deposit: u64 = query_contract(
+            &mut builder,
+            "get_creation_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(deposit, 0);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "claim_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            134,
+        );
+    }
+
+    #[test]
+    fn should_forfeit_deposit_of_abandoned_escrow() {
+        let fee_receiver = AccountHash::new([61; 32]);
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_CREATION_DEPOSIT => 50u64,
+            ARG_DEPOSIT_TIMEOUT => 1_000u64,
+            ARG_FEE_RECEIVER => fee_receiver,
+        });
+        create_funded_account(&mut builder, 61);
+
+        set_block_time(10_000);
+        create_escrow_with_deposit(&mut builder, 50);
+
+        set_block_time(10_500);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "forfeit_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            136,
+        );
+
+        let receiver_purse = builder
+            .get_account(fee_receiver)
+            .expect("should have account")
+            .main_purse();
+        let receiver_before = builder.get_purse_balance(receiver_purse);
+
+        set_block_time(11_000);
+        call_contract(
+            &mut builder,
+            "forfeit_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        assert_eq!(
+            builder.get_purse_balance(receiver_purse),
+            receiver_before + 50
+        );
+        let deposit: u64 = query_contract(
+            &mut builder,
+            "get_creation_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(deposit, 0);
+    }
+
+    #[test]
+    fn should_complete_shrunk_escrow_with_fewer_participants() {
+        let mut builder = install_contract();
+        let friends = [
+            create_funded_account(&mut builder, 71),
+            create_funded_account(&mut builder, 72),
+        ];
+        create_escrow(&mut builder, 1, 100, 500);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        for friend in friends {
+            call_with_purse_as(
+                &mut builder,
+                friend,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+            );
+        }
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "shrink_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_NUM_PARTICIPANTS => 2u64 },
+            138,
+        );
+
+        call_contract(
+            &mut builder,
+            "shrink_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_NUM_PARTICIPANTS => 3u64 },
+        );
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        assert_eq!(escrows_by_status(&mut builder, 2, 0, 10), vec![1]);
+    }
+
+    #[test]
+    fn should_cancel_disputed_escrow_on_majority_vote() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_SETTLEMENT_DELAY => 1_000u64,
+        });
+        let friends = [
+            create_funded_account(&mut builder, 81),
+            create_funded_account(&mut builder, 82),
+        ];
+        create_escrow(&mut builder, 1, 100, 300);
+
+        set_block_time(10_000);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        for friend in friends {
+            call_with_purse_as(
+                &mut builder,
+                friend,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+            );
+        }
+        call_contract(
+            &mut builder,
+            "raise_dispute",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        call_contract(
+            &mut builder,
+            "vote_cancel",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "vote_cancel",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            140,
+        );
+        assert_eq!(escrows_by_status(&mut builder, 3, 0, 10), vec![1]);
+
+        let bystander_purse = builder
+            .get_account(friends[1])
+            .expect("should have account")
+            .main_purse();
+        let bystander_before = builder.get_purse_balance(bystander_purse);
+        call_contract_as(
+            &mut builder,
+            friends[0],
+            "vote_cancel",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        let votes: u64 = query_contract(
+            &mut builder,
+            "get_cancel_votes",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(votes, 2);
+        assert_eq!(escrows_by_status(&mut builder, 4, 0, 10), vec![1]);
+        assert_eq!(
+            builder.get_purse_balance(bystander_purse),
+            bystander_before + 100
+        );
+    }
+
+    #[test]
+    fn should_apply_default_deadline_when_none_given() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_DEFAULT_DEADLINE_OFFSET => 5_000u64,
+        });
+
+        set_block_time(10_000);
+        create_escrow(&mut builder, 1, 100, 300);
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 2u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_DEADLINE => 0u64,
+            },
+        );
+
+        let defaulted: u64 = query_contract(
+            &mut builder,
+            "get_deadline",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(defaulted, 15_000);
+        let no_deadline: u64 = query_contract(
+            &mut builder,
+            "get_deadline",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(no_deadline, 0);
+    }
+
+    #[test]
+    fn should_record_install_time() {
+        set_block_time(42_000);
+        let mut builder = install_contract();
+
+        set_block_time(50_000);
+        create_escrow(&mut builder, 1, 100, 300);
+
+        let installed_at: u64 =
+            query_contract(&mut builder, "get_install_time", RuntimeArgs::new());
+        assert_eq!(installed_at, 42_000);
+    }
+
+    #[test]
+    fn should_rate_limit_participant_nudges() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_NUDGE_INTERVAL => 1_000u64,
+        });
+        let friend = create_funded_account(&mut builder, 91);
+        create_escrow(&mut builder, 1, 100, 300);
+
+        assert_user_error(
+            &mut builder,
+            friend,
+            "nudge",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            111,
+        );
+        call_contract_as(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        set_block_time(10_000);
+        call_contract_as(
+            &mut builder,
+            friend,
+            "nudge",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        let event: String = read_contract_named_value(&builder, "event_nudge_4");
+        assert_eq!(event, format!("from:{},remaining_slots:3", friend));
+
+        set_block_time(10_500);
+        assert_user_error(
+            &mut builder,
+            friend,
+            "nudge",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            141,
+        );
+
+        set_block_time(11_000);
+        call_contract_as(
+            &mut builder,
+            friend,
+            "nudge",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+    }
+
+    #[test]
+    fn should_route_yield_dust_on_completion() {
+        let dust_recipient = AccountHash::new([101; 32]);
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_DUST_RECIPIENT => dust_recipient,
+        });
+        create_funded_account(&mut builder, 101);
+        let friends = [
+            create_funded_account(&mut builder, 102),
+            create_funded_account(&mut builder, 103),
+        ];
+        create_escrow(&mut builder, 1, 100, 300);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        for friend in friends {
+            call_with_purse_as(
+                &mut builder,
+                friend,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+            );
+        }
+        call_with_purse(
+            &mut builder,
+            "deposit_yield",
+            10,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 10u64 },
+        );
+
+        let recipient_purse = builder
+            .get_account(dust_recipient)
+            .expect("should have account")
+            .main_purse();
+        let recipient_before = builder.get_purse_balance(recipient_purse);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        // Each participant's share of 10 is 100 * 10 / 300 = 3, leaving 1.
+        let dust: u64 = query_contract(
+            &mut builder,
+            "get_dust",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(dust, 1);
+        assert_eq!(
+            builder.get_purse_balance(recipient_purse),
+            recipient_before + 1
+        );
+
+        let account_purse = builder
+            .get_account(*DEFAULT_ACCOUNT_ADDR)
+            .expect("should have account")
+            .main_purse();
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "deposit_yield",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 10u64,
+                "purse" => account_purse,
+            },
+            100,
+        );
+
+        for participant in [*DEFAULT_ACCOUNT_ADDR, friends[0], friends[1]] {
+            call_with_purse_as(
+                &mut builder,
+                participant,
+                "withdraw",
+                0,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => participant },
+            );
+            let participant_yield: u64 = query_contract(
+                &mut builder,
+                "get_participant_yield",
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => participant },
+            );
+            assert_eq!(participant_yield, 3);
+        }
+    }
+
+    #[test]
+    fn should_hold_conditional_escrow_and_refund_after_deadline() {
+        let mut builder = install_contract();
+        // Any stored contract without `is_condition_met` stands in for an oracle here.
+        let oracle = ContractHash::new(contract_hash(&builder).value());
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_DEADLINE => 20_000u64,
+                ARG_ORACLE => oracle,
+                ARG_CONDITION_KEY => "flight_landed".to_string(),
+            },
+        );
+
+        set_block_time(10_000);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            142,
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "refund_unmet_condition",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            144,
+        );
+        assert_eq!(escrows_by_status(&mut builder, 1, 0, 10), vec![1]);
+
+        set_block_time(20_000);
+        call_contract(
+            &mut builder,
+            "refund_unmet_condition",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(escrows_by_status(&mut builder, 4, 0, 10), vec![1]);
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 100);
+    }
+
+    #[test]
+    fn should_keep_or_return_overpaid_installments() {
+        let mut builder = install_contract();
+        for (escrow_id, keep_excess) in [(1u64, false), (2u64, true)] {
+            call_contract(
+                &mut builder,
+                "create_escrow",
+                runtime_args! {
+                    ARG_ESCROW_ID => escrow_id,
+                    ARG_AMOUNT => 100u64,
+                    ARG_TARGET_AMOUNT => 300u64,
+                    ARG_KEEP_EXCESS => keep_excess,
+                },
+            );
+            call_with_purse(
+                &mut builder,
+                "pay_installment",
+                150,
+                runtime_args! { ARG_ESCROW_ID => escrow_id, ARG_AMOUNT => 150u64 },
+            );
+            let paid: u64 = query_contract(
+                &mut builder,
+                "get_participant_paid",
+                runtime_args! {
+                    ARG_ESCROW_ID => escrow_id,
+                    ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR,
+                },
+            );
+            assert_eq!(paid, 100);
+        }
+
+        let returned_yield: u64 = read_contract_named_value(&builder, "escrow_1_total_yield");
+        assert_eq!(returned_yield, 0);
+        let kept_yield: u64 = read_contract_named_value(&builder, "escrow_2_total_yield");
+        assert_eq!(kept_yield, 50);
+    }
+
+    #[test]
+    fn should_clone_completed_escrow_into_fresh_one() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 111);
+
+        set_block_time(10_000);
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_DEADLINE => 15_000u64,
+                ARG_MEMO => "Friday dinner".to_string(),
+            },
+        );
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        set_block_time(30_000);
+        assert_user_error(
+            &mut builder,
+            friend,
+            "clone_escrow",
+            runtime_args! { ARG_SOURCE_ESCROW_ID => 1u64, ARG_ESCROW_ID => 2u64 },
+            117,
+        );
+        call_contract(
+            &mut builder,
+            "clone_escrow",
+            runtime_args! { ARG_SOURCE_ESCROW_ID => 1u64, ARG_ESCROW_ID => 2u64 },
+        );
+
+        assert_eq!(escrows_by_status(&mut builder, 0, 0, 10), vec![2]);
+        let deadline: u64 = query_contract(
+            &mut builder,
+            "get_deadline",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(deadline, 35_000);
+        let memo: String = read_contract_named_value(&builder, "escrow_2_memo");
+        assert_eq!(memo, "Friday dinner");
+        let split: u64 = read_contract_named_value(&builder, "escrow_2");
+        assert_eq!(split, 100);
+        let total_staked: u64 = read_contract_named_value(&builder, "escrow_2_total_staked");
+        assert_eq!(total_staked, 0);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "clone_escrow",
+            runtime_args! { ARG_SOURCE_ESCROW_ID => 2u64, ARG_ESCROW_ID => 1u64 },
+            181,
+        );
+        let total_staked: u64 = read_contract_named_value(&builder, "escrow_1_total_staked");
+        assert_eq!(total_staked, 100);
+    }
+
+    #[test]
+    fn should_refund_large_escrow_in_batches() {
+        let mut builder = install_contract();
+        let friends: Vec<AccountHash> = (121..130)
+            .map(|seed| create_funded_account(&mut builder, seed))
+            .collect();
+        create_escrow(&mut builder, 1, 100, 2_000);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        for friend in &friends {
+            call_with_purse_as(
+                &mut builder,
+                *friend,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+            );
+        }
+
+        let last_purse = builder
+            .get_account(friends[8])
+            .expect("should have account")
+            .main_purse();
+        let last_before = builder.get_purse_balance(last_purse);
+
+        call_contract(
+            &mut builder,
+            "refund_batch",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_MAX_COUNT => 5u8 },
+        );
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 500);
+        assert_eq!(builder.get_purse_balance(last_purse), last_before);
+        assert!(escrows_by_status(&mut builder, 4, 0, 10).is_empty());
+
+        call_contract(
+            &mut builder,
+            "refund_batch",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_MAX_COUNT => 5u8 },
+        );
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 1_000);
+        assert_eq!(builder.get_purse_balance(last_purse), last_before + 100);
+        assert_eq!(escrows_by_status(&mut builder, 4, 0, 10), vec![1]);
+    }
+
+    #[test]
+    fn should_reject_joins_while_refunding() {
+        let mut builder = install_contract();
+        let friends = [
+            create_funded_account(&mut builder, 131),
+            create_funded_account(&mut builder, 132),
+        ];
+        create_escrow(&mut builder, 1, 100, 500);
+        for friend in friends {
+            call_with_purse_as(
+                &mut builder,
+                friend,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+            );
+        }
+
+        call_contract(
+            &mut builder,
+            "refund_batch",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_MAX_COUNT => 1u8 },
+        );
+        assert_eq!(escrows_by_status(&mut builder, 5, 0, 10), vec![1]);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            145,
+        );
+        let account_purse = builder
+            .get_account(*DEFAULT_ACCOUNT_ADDR)
+            .expect("should have account")
+            .main_purse();
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "pay_installment",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                "purse" => account_purse,
+            },
+            145,
+        );
+
+        call_contract(
+            &mut builder,
+            "refund_batch",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_MAX_COUNT => 1u8 },
+        );
+        assert!(escrows_by_status(&mut builder, 5, 0, 10).is_empty());
+        assert_eq!(escrows_by_status(&mut builder, 4, 0, 10), vec![1]);
+    }
+
+    #[test]
+    fn should_answer_ping() {
+        let mut builder = install_contract();
+
+        let response: u64 = query_contract(&mut builder, "ping", RuntimeArgs::new());
+        assert_eq!(response, 1);
+    }
+
+    #[test]
+    fn should_reject_joins_while_escrow_paused() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 141);
+        create_escrow(&mut builder, 1, 100, 300);
+        create_escrow(&mut builder, 2, 100, 300);
+
+        assert_user_error(
+            &mut builder,
+            friend,
+            "pause_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            117,
+        );
+        call_contract(
+            &mut builder,
+            "pause_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        let paused: bool = query_contract(
+            &mut builder,
+            "get_escrow_paused",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert!(paused);
+
+        assert_user_error(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            147,
+        );
+        call_contract_as(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+
+        call_contract(
+            &mut builder,
+            "unpause_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        call_contract_as(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+    }
+
+    #[test]
+    fn should_report_escrow_timing() {
+        let mut builder = install_contract();
+
+        set_block_time(10_000);
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_DEADLINE => 15_000u64,
+            },
+        );
+        create_escrow(&mut builder, 2, 100, 300);
+
+        set_block_time(12_000);
+        let timing: BTreeMap<String, u64> = query_contract(
+            &mut builder,
+            "get_timing",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(timing["created_at"], 10_000);
+        assert_eq!(timing["deadline"], 15_000);
+        assert_eq!(timing["now"], 12_000);
+        assert_eq!(timing["remaining"], 3_000);
+
+        let open_ended: BTreeMap<String, u64> = query_contract(
+            &mut builder,
+            "get_timing",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(open_ended["remaining"], u64::MAX);
+
+        set_block_time(20_000);
+        let expired: BTreeMap<String, u64> = query_contract(
+            &mut builder,
+            "get_timing",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(expired["remaining"], 0);
+    }
+
+    #[test]
+    fn should_store_encrypted_memo_as_opaque_bytes() {
+        let mut builder = install_contract();
+        let ciphertext = Bytes::from(vec![0x9f, 0x00, 0x42, 0xff, 0x13]);
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_ENCRYPTED_MEMO => ciphertext.clone(),
+            },
+        );
+        create_escrow(&mut builder, 2, 100, 300);
+
+        let stored: Bytes = query_contract(
+            &mut builder,
+            "get_encrypted_memo",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(stored, ciphertext);
+        let none: Bytes = query_contract(
+            &mut builder,
+            "get_encrypted_memo",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert!(none.is_empty());
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 3u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_ENCRYPTED_MEMO => Bytes::from(vec![0u8; 1025]),
+            },
+            148,
+        );
+    }
+
+    #[test]
+    fn should_install_prefixed_instances_side_by_side() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_NAME_PREFIX => "test".to_string(),
+        });
+        let install_request = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            CONTRACT_WASM,
+            runtime_args! { ARG_NAME_PREFIX => "prod".to_string() },
+        )
+        .build();
+        builder.exec(install_request).commit().expect_success();
+
+        let named_keys = builder.get_named_keys_by_account_hash(*DEFAULT_ACCOUNT_ADDR);
+        assert!(named_keys.get(CONTRACT_KEY).is_none());
+        let instances: Vec<AddressableEntityHash> = ["test", "prod"]
+            .iter()
+            .map(|prefix| {
+                named_keys
+                    .get(&format!("{}_{}", prefix, CONTRACT_KEY))
+                    .and_then(|key| key.into_hash_addr())
+                    .map(AddressableEntityHash::new)
+                    .expect("should have prefixed contract hash")
+            })
+            .collect();
+        assert_ne!(instances[0], instances[1]);
+        assert!(named_keys.get("test_group_escrow_package").is_some());
+        assert!(named_keys.get("prod_group_escrow_package").is_some());
+
+        let create_request = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            instances[0],
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+            },
+        )
+        .build();
+        builder.exec(create_request).commit().expect_success();
+
+        let exists_in = |builder: &mut LmdbWasmTestBuilder, instance| -> bool {
+            let request = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                instance,
+                "escrow_exists",
+                runtime_args! { ARG_ESCROW_ID => 1u64 },
+            )
+            .build();
+            builder.exec(request).commit().expect_success();
+            builder
+                .get_last_exec_result()
+                .expect("should have exec result")
+                .ret()
+                .expect("should return a value")
+                .clone()
+                .into_t()
+                .expect("should be bool")
+        };
+        assert!(exists_in(&mut builder, instances[0]));
+        assert!(!exists_in(&mut builder, instances[1]));
+    }
+
+    #[test]
+    fn should_list_package_versions() {
+        let mut builder = install_contract();
+
+        let versions: Vec<u32> = query_contract(&mut builder, "get_versions", RuntimeArgs::new());
+        assert_eq!(versions, vec![1]);
+
+        let stored: Vec<u32> = read_contract_named_value(&builder, "versions");
+        assert_eq!(stored, versions);
+    }
+
+    #[test]
+    fn should_invite_waitlisted_account_when_slot_frees() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 142);
+        let waiter = create_funded_account(&mut builder, 143);
+        create_escrow(&mut builder, 1, 100, 200);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            waiter,
+            "join_waitlist",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            149,
+        );
+
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_contract_as(
+            &mut builder,
+            waiter,
+            "join_waitlist",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            waiter,
+            "join_waitlist",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            150,
+        );
+        let waitlist: Vec<AccountHash> = query_contract(
+            &mut builder,
+            "get_waitlist",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(waitlist, vec![waiter]);
+
+        let events_before: u64 = read_contract_named_value(&builder, "escrow_1_event_counter");
+        call_with_purse(
+            &mut builder,
+            "refund",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+
+        let event: String =
+            read_contract_named_value(&builder, &format!("event_slot_open_{}", events_before + 1));
+        assert_eq!(event, format!("invitee:{}", waiter));
+        let waitlist: Vec<AccountHash> = query_contract(
+            &mut builder,
+            "get_waitlist",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert!(waitlist.is_empty());
+    }
+
+    #[test]
+    fn should_preview_settlement_without_moving_funds() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 144);
+        call_contract_as(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 200u64,
+                ARG_TARGET_AMOUNT => 200u64,
+                ARG_SETTLE_TO_CREATOR => true,
+            },
+        );
+        assert_user_error(
+            &mut builder,
+            creator,
+            "preview_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            110,
+        );
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            200,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 200u64 },
+        );
+        call_with_purse(
+            &mut builder,
+            "deposit_yield",
+            20,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 20u64 },
+        );
+
+        let preview: (u64, u64, u64) = query_contract(
+            &mut builder,
+            "preview_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(preview, (220, 0, 220));
+        let balance: u64 = query_contract(
+            &mut builder,
+            "get_escrow_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(balance, 200);
+
+        let creator_purse = builder
+            .get_account(creator)
+            .expect("should have account")
+            .main_purse();
+        let balance_before = builder.get_purse_balance(creator_purse);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(
+            builder.get_purse_balance(creator_purse),
+            balance_before + preview.2
+        );
+    }
+
+    #[test]
+    fn should_open_next_period_when_recurring_escrow_completes() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 145);
+        call_contract_as(
+            &mut builder,
+            creator,
+            "create_recurring_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_MEMO => "streaming",
+            },
+        );
+        create_escrow(&mut builder, 2, 50, 100);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        let successor_creator: AccountHash =
+            read_contract_named_value(&builder, "escrow_3_creator");
+        assert_eq!(successor_creator, creator);
+        let memo: String = read_contract_named_value(&builder, "escrow_3_memo");
+        assert_eq!(memo, "streaming");
+        assert_eq!(escrows_by_status(&mut builder, 0, 0, 10), vec![2, 3]);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            100,
+        );
+        let exists: bool = query_contract(
+            &mut builder,
+            "escrow_exists",
+            runtime_args! { ARG_ESCROW_ID => 4u64 },
+        );
+        assert!(!exists);
+
+        let chain: Vec<u64> = query_contract(
+            &mut builder,
+            "get_recurrence_chain",
+            runtime_args! { ARG_ESCROW_ID => 3u64 },
+        );
+        assert_eq!(chain, vec![1, 3]);
+        let chain: Vec<u64> = query_contract(
+            &mut builder,
+            "get_recurrence_chain",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(chain, vec![2]);
+    }
+
+    #[test]
+    fn should_reject_splits_that_round_to_zero() {
+        let mut builder = install_contract();
+        let caterer = create_funded_account(&mut builder, 146);
+        call_contract(
+            &mut builder,
+            "save_template",
+            runtime_args! {
+                ARG_TEMPLATE_NAME => "big_group".to_string(),
+                ARG_NUM_PARTICIPANTS => 10u64,
+            },
+        );
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_from_template",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_TEMPLATE_NAME => "big_group".to_string(),
+                ARG_TARGET_AMOUNT => 5u64,
+            },
+            152,
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_escrow_multi_recipient",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_RECIPIENTS => vec![(caterer, 100u64), (*DEFAULT_ACCOUNT_ADDR, 0u64)],
+            },
+            152,
+        );
+    }
+
+    #[test]
+    fn should_pay_refunds_to_updated_refund_address() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 147);
+        let savings = create_funded_account(&mut builder, 148);
+        create_escrow(&mut builder, 1, 100, 300);
+
+        assert_user_error(
+            &mut builder,
+            friend,
+            "update_refund_address",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_NEW_REFUND => savings },
+            111,
+        );
+        call_contract_as(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        let refund_address: AccountHash = query_contract(
+            &mut builder,
+            "get_refund_address",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => friend },
+        );
+        assert_eq!(refund_address, friend);
+
+        call_contract_as(
+            &mut builder,
+            friend,
+            "update_refund_address",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_NEW_REFUND => savings },
+        );
+        let refund_address: AccountHash = query_contract(
+            &mut builder,
+            "get_refund_address",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => friend },
+        );
+        assert_eq!(refund_address, savings);
+
+        let savings_purse = builder
+            .get_account(savings)
+            .expect("should have account")
+            .main_purse();
+        let savings_before = builder.get_purse_balance(savings_purse);
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "refund",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => friend },
+        );
+        assert_eq!(
+            builder.get_purse_balance(savings_purse),
+            savings_before + 100
+        );
+    }
+
+    #[test]
+    fn should_rebate_gas_to_participants_from_creator_proceeds() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 149);
+        let friend = create_funded_account(&mut builder, 150);
+        assert_user_error(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 200u64,
+                ARG_GAS_REBATE => 101u64,
+            },
+            153,
+        );
+        call_contract_as(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 200u64,
+                ARG_SETTLE_TO_CREATOR => true,
+                ARG_GAS_REBATE => 5u64,
+            },
+        );
+        let gas_rebate: u64 = query_contract(
+            &mut builder,
+            "get_gas_rebate",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(gas_rebate, 5);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        let preview: (u64, u64, u64) = query_contract(
+            &mut builder,
+            "preview_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(preview, (200, 0, 190));
+
+        let creator_purse = builder
+            .get_account(creator)
+            .expect("should have account")
+            .main_purse();
+        let friend_purse = builder
+            .get_account(friend)
+            .expect("should have account")
+            .main_purse();
+        let creator_before = builder.get_purse_balance(creator_purse);
+        let friend_before = builder.get_purse_balance(friend_purse);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        assert_eq!(
+            builder.get_purse_balance(creator_purse),
+            creator_before + 190
+        );
+        assert_eq!(builder.get_purse_balance(friend_purse), friend_before + 5);
+        let rebates_paid: u64 = read_contract_named_value(&builder, "escrow_1_gas_rebates_paid");
+        assert_eq!(rebates_paid, 10);
+    }
+
+    #[test]
+    fn should_purge_settled_escrow() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 151);
+        call_contract_as(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_SETTLE_TO_CREATOR => true,
+            },
+        );
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "purge_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            154,
+        );
+
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            creator,
+            "purge_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            107,
+        );
+        call_contract(
+            &mut builder,
+            "purge_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        let exists: bool = query_contract(
+            &mut builder,
+            "escrow_exists",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert!(!exists);
+        assert!(escrows_by_status(&mut builder, 2, 0, 10).is_empty());
+        let contract = builder
+            .query(None, Key::Hash(contract_hash(&builder).value()), &[])
+            .expect("should have contract");
+        let named_keys = contract
+            .as_contract()
+            .expect("should be contract")
+            .named_keys();
+        assert!(!named_keys
+            .names()
+            .any(|name| name.starts_with("escrow_1_") && !name.starts_with("escrow_1_event")));
+    }
+
+    #[test]
+    fn should_report_whether_account_can_join() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 152);
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_EXCLUDE_CREATOR => true,
+            },
+        );
+
+        let creator_check: (bool, u8) = query_contract(
+            &mut builder,
+            "can_join",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_ACCOUNT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_eq!(creator_check, (false, 115));
+        let friend_check: (bool, u8) = query_contract(
+            &mut builder,
+            "can_join",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_ACCOUNT => friend },
+        );
+        assert_eq!(friend_check, (true, 0));
+
+        call_contract(
+            &mut builder,
+            "pause_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        let friend_check: (bool, u8) = query_contract(
+            &mut builder,
+            "can_join",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_ACCOUNT => friend },
+        );
+        assert_eq!(friend_check, (false, 147));
+        assert_user_error(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            friend_check.1.into(),
+        );
+    }
+
+    fn create_open_pot(builder: &mut LmdbWasmTestBuilder, creator: AccountHash) {
+        call_contract_as(
+            builder,
+            creator,
+            "create_open_pot",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_MIN_CONTRIBUTION => 30u64,
+            },
+        );
+    }
+
+    #[test]
+    fn should_fill_open_pot_exactly() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 153);
+        let friend = create_funded_account(&mut builder, 154);
+        create_open_pot(&mut builder, creator);
+
+        call_with_purse(
+            &mut builder,
+            "contribute",
+            60,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 60u64 },
+        );
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "contribute",
+            40,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 40u64 },
+        );
+
+        let pot: u64 = query_contract(
+            &mut builder,
+            "get_pot_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(pot, 100);
+        assert_eq!(escrows_by_status(&mut builder, 1, 0, 10), vec![1]);
+
+        let creator_purse = builder
+            .get_account(creator)
+            .expect("should have account")
+            .main_purse();
+        let creator_before = builder.get_purse_balance(creator_purse);
+        call_contract(
+            &mut builder,
+            "finalize_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(
+            builder.get_purse_balance(creator_purse),
+            creator_before + 100
+        );
+    }
+
+    #[test]
+    fn should_return_open_pot_overshoot_to_last_contributor() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 155);
+        let friend = create_funded_account(&mut builder, 156);
+        create_open_pot(&mut builder, creator);
+
+        assert_user_error(
+            &mut builder,
+            friend,
+            "contribute",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 20u64 },
+            156,
+        );
+        call_with_purse(
+            &mut builder,
+            "contribute",
+            80,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 80u64 },
+        );
+
+        let friend_purse = builder
+            .get_account(friend)
+            .expect("should have account")
+            .main_purse();
+        let friend_before = builder.get_purse_balance(friend_purse);
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "contribute",
+            50,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 50u64 },
+        );
+        assert_eq!(builder.get_purse_balance(friend_purse), friend_before - 20);
+
+        let pot: u64 = query_contract(
+            &mut builder,
+            "get_pot_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(pot, 100);
+        let stake: u64 = read_contract_named_value(&builder, &format!("escrow_1_stake_{}", friend));
+        assert_eq!(stake, 20);
+    }
+
+    #[test]
+    fn should_sum_repeat_open_pot_contributions() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 157);
+        let friend = create_funded_account(&mut builder, 158);
+        create_open_pot(&mut builder, creator);
+
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "contribute",
+            30,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 30u64 },
+        );
+        call_with_purse(
+            &mut builder,
+            "contribute",
+            40,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 40u64 },
+        );
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "contribute",
+            30,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 30u64 },
+        );
+
+        let contributions: Vec<(AccountHash, u64)> = query_contract(
+            &mut builder,
+            "get_contributions",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_OFFSET => 0u64, ARG_LIMIT => 10u64 },
+        );
+        assert_eq!(
+            contributions,
+            vec![(friend, 60), (*DEFAULT_ACCOUNT_ADDR, 40)]
+        );
+        let second_page: Vec<(AccountHash, u64)> = query_contract(
+            &mut builder,
+            "get_contributions",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_OFFSET => 1u64, ARG_LIMIT => 1u64 },
+        );
+        assert_eq!(second_page, vec![(*DEFAULT_ACCOUNT_ADDR, 40)]);
+    }
+
+    #[test]
+    fn should_round_trip_creator_metadata() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 159);
+        create_escrow(&mut builder, 1, 100, 300);
+
+        call_contract(
+            &mut builder,
+            "set_metadata",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_KEY => "category",
+                ARG_VALUE => "dining",
+            },
+        );
+        let value: String = query_contract(
+            &mut builder,
+            "get_metadata",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_KEY => "category" },
+        );
+        assert_eq!(value, "dining");
+        let unset: String = query_contract(
+            &mut builder,
+            "get_metadata",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_KEY => "external_id" },
+        );
+        assert_eq!(unset, "");
+
+        assert_user_error(
+            &mut builder,
+            friend,
+            "set_metadata",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_KEY => "category",
+                ARG_VALUE => "travel",
+            },
+            117,
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "set_metadata",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_KEY => "k".repeat(65),
+                ARG_VALUE => "too long",
+            },
+            157,
+        );
+    }
+
+    #[test]
+    fn should_settle_into_creator_settlement_purse() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 160);
+        let creator_purse = builder
+            .get_account(creator)
+            .expect("should have account")
+            .main_purse();
+
+        assert_user_error(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 200u64,
+                ARG_TARGET_AMOUNT => 200u64,
+                ARG_SETTLEMENT_PURSE => creator_purse.into_read(),
+            },
+            158,
+        );
+
+        call_contract_as(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 200u64,
+                ARG_TARGET_AMOUNT => 200u64,
+                ARG_SETTLE_TO_CREATOR => true,
+                ARG_SETTLEMENT_PURSE => creator_purse.into_add(),
+            },
+        );
+        let settlement_purse: Option<URef> = query_contract(
+            &mut builder,
+            "get_settlement_purse",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(settlement_purse, Some(creator_purse.into_add()));
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            200,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 200u64 },
+        );
+        let balance_before = builder.get_purse_balance(creator_purse);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(
+            builder.get_purse_balance(creator_purse),
+            balance_before + 200
+        );
+    }
+
+    #[test]
+    fn should_rate_limit_escrow_creation_per_window() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_CREATION_RATE_LIMIT => 2u64,
+            ARG_CREATION_RATE_WINDOW => 10_000u64,
+        });
+        create_escrow(&mut builder, 1, 100, 300);
+        create_escrow(&mut builder, 2, 100, 300);
+
+        let allowance: u64 =
+            query_contract(&mut builder, "get_creation_allowance", RuntimeArgs::new());
+        assert_eq!(allowance, 0);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 3u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+            },
+            159,
+        );
+
+        set_block_time(block_time() + 10_000);
+        let allowance: u64 =
+            query_contract(&mut builder, "get_creation_allowance", RuntimeArgs::new());
+        assert_eq!(allowance, 2);
+        create_escrow(&mut builder, 3, 100, 300);
+        let allowance: u64 =
+            query_contract(&mut builder, "get_creation_allowance", RuntimeArgs::new());
+        assert_eq!(allowance, 1);
+    }
+
+    #[test]
+    fn should_return_own_contract_hash() {
+        let mut builder = install_contract();
+
+        let self_hash: ContractHash =
+            query_contract(&mut builder, "get_self_hash", RuntimeArgs::new());
+        assert_eq!(self_hash.value(), contract_hash(&builder).value());
+    }
+
+    #[test]
+    fn should_auto_cancel_idle_escrow_and_pay_keeper_bounty() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_IDLE_CANCEL_WINDOW => 1_000u64,
+            ARG_IDLE_CANCEL_BOUNTY => 10u64,
+        });
+        let keeper = create_funded_account(&mut builder, 161);
+        call_with_purse(
+            &mut builder,
+            "fund_fee_pool",
+            50,
+            runtime_args! { ARG_AMOUNT => 50u64 },
+        );
+        create_escrow(&mut builder, 1, 100, 300);
+        set_block_time(block_time() + 500);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+
+        set_block_time(block_time() + 999);
+        assert_user_error(
+            &mut builder,
+            keeper,
+            "auto_cancel_idle",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            160,
+        );
+
+        set_block_time(block_time() + 1);
+        call_contract_as(
+            &mut builder,
+            keeper,
+            "auto_cancel_idle",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(escrows_by_status(&mut builder, 4, 0, 10), vec![1]);
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 100);
+        let fee_pool: u64 = query_contract(&mut builder, "get_fee_pool", RuntimeArgs::new());
+        assert_eq!(fee_pool, 40);
+    }
+
+    #[test]
+    fn should_settle_partial_pool_to_creator_at_deadline() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 162);
+        let friend = create_funded_account(&mut builder, 163);
+        let late_friend = create_funded_account(&mut builder, 164);
+        let deadline = block_time() + 1_000;
+        call_contract_as(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 500u64,
+                ARG_DEADLINE => deadline,
+                ARG_SETTLE_PARTIAL_AT_DEADLINE => true,
+            },
+        );
+        for participant in [*DEFAULT_ACCOUNT_ADDR, friend] {
+            call_with_purse_as(
+                &mut builder,
+                participant,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+            );
+        }
+        call_with_purse_as(
+            &mut builder,
+            late_friend,
+            "pay_installment",
+            40,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 40u64 },
+        );
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "settle_at_deadline",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            162,
+        );
+
+        set_block_time(deadline);
+        let creator_purse = builder
+            .get_account(creator)
+            .expect("should have account")
+            .main_purse();
+        let balance_before = builder.get_purse_balance(creator_purse);
+        call_contract(
+            &mut builder,
+            "settle_at_deadline",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(
+            builder.get_purse_balance(creator_purse),
+            balance_before + 200
+        );
+        assert_eq!(escrows_by_status(&mut builder, 2, 0, 10), vec![1]);
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 40);
+    }
+
+    #[test]
+    fn should_transfer_stake_between_escrows() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 300);
+        create_escrow(&mut builder, 2, 100, 300);
+        create_escrow(&mut builder, 3, 50, 300);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "transfer_stake",
+            runtime_args! { ARG_SOURCE_ESCROW_ID => 1u64, ARG_ESCROW_ID => 3u64 },
+            163,
+        );
+
+        call_contract(
+            &mut builder,
+            "transfer_stake",
+            runtime_args! { ARG_SOURCE_ESCROW_ID => 1u64, ARG_ESCROW_ID => 2u64 },
+        );
+        for (escrow_id, expected) in [(1u64, 0u64), (2, 100)] {
+            let pot: u64 = query_contract(
+                &mut builder,
+                "get_pot_balance",
+                runtime_args! { ARG_ESCROW_ID => escrow_id },
+            );
+            assert_eq!(pot, expected);
+        }
+        let participants: u64 = query_contract(
+            &mut builder,
+            "get_participant_count",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(participants, 1);
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 0);
+    }
+
+    #[test]
+    fn should_report_cspr_decimals() {
+        let mut builder = install_contract();
+
+        let decimals: u8 = query_contract(&mut builder, "get_decimals", runtime_args! {});
+        assert_eq!(decimals, 9);
+    }
+
+    #[test]
+    fn should_check_memberships_across_escrows() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 300);
+        create_escrow(&mut builder, 2, 100, 300);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 2u64, ARG_AMOUNT => 100u64 },
+        );
+
+        let memberships: Vec<bool> = query_contract(
+            &mut builder,
+            "check_memberships",
+            runtime_args! {
+                ARG_ESCROW_IDS => vec![1u64, 2, 99],
+                ARG_ACCOUNT => *DEFAULT_ACCOUNT_ADDR,
+            },
+        );
+        assert_eq!(memberships, vec![false, true, false]);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "check_memberships",
+            runtime_args! {
+                ARG_ESCROW_IDS => (0u64..51).collect::<Vec<u64>>(),
+                ARG_ACCOUNT => *DEFAULT_ACCOUNT_ADDR,
+            },
+            118,
+        );
+    }
+
+    #[test]
+    fn should_reject_joins_after_deadline() {
+        let mut builder = install_contract();
+        let deadline = block_time() + 1_000;
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_DEADLINE => deadline,
+            },
+        );
+
+        set_block_time(deadline);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            165,
+        );
+        assert_session_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "stake",
+            100,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR,
+            },
+            165,
+        );
+    }
+
+    #[test]
+    fn should_revive_expired_escrow_keeping_participants() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 165);
+        let late_friend = create_funded_account(&mut builder, 166);
+        let deadline = block_time() + 1_000;
+        for escrow_id in [1u64, 2] {
+            call_contract(
+                &mut builder,
+                "create_escrow",
+                runtime_args! {
+                    ARG_ESCROW_ID => escrow_id,
+                    ARG_AMOUNT => 100u64,
+                    ARG_TARGET_AMOUNT => 300u64,
+                    ARG_DEADLINE => deadline,
+                },
+            );
+            for participant in [*DEFAULT_ACCOUNT_ADDR, friend] {
+                call_with_purse_as(
+                    &mut builder,
+                    participant,
+                    "pay_installment",
+                    100,
+                    runtime_args! { ARG_ESCROW_ID => escrow_id, ARG_AMOUNT => 100u64 },
+                );
+            }
+        }
+
+        set_block_time(deadline);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "revive_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_DEADLINE => deadline },
+            168,
+        );
+        call_contract(
+            &mut builder,
+            "revive_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_DEADLINE => deadline + 1_000 },
+        );
+        let participants: u64 = query_contract(
+            &mut builder,
+            "get_participant_count",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(participants, 2);
+        call_with_purse_as(
+            &mut builder,
+            late_friend,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        let pot: u64 = query_contract(
+            &mut builder,
+            "get_pot_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(pot, 300);
+
+        call_with_purse(
+            &mut builder,
+            "refund",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 2u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "revive_escrow",
+            runtime_args! { ARG_ESCROW_ID => 2u64, ARG_DEADLINE => deadline + 1_000 },
+            167,
+        );
+    }
+
+    #[test]
+    fn should_scale_leave_fee_with_time_held() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_LEAVE_FEE_BPS => 0u64,
+            ARG_LATE_LEAVE_FEE_BPS => 2_000u64,
+        });
+        let deadline = block_time() + 1_000;
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_DEADLINE => deadline,
+            },
+        );
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        let preview = |builder: &mut LmdbWasmTestBuilder| -> u64 {
+            query_contract(
+                builder,
+                "preview_leave_refund",
+                runtime_args! {
+                    ARG_ESCROW_ID => 1u64,
+                    ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR,
+                },
+            )
+        };
+
+        set_block_time(deadline - 900);
+        assert_eq!(preview(&mut builder), 98);
+        set_block_time(deadline - 250);
+        assert_eq!(preview(&mut builder), 85);
+
+        call_with_purse(
+            &mut builder,
+            "refund",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 85);
+    }
+
+    #[test]
+    fn should_let_escrow_admin_pause_and_resolve_disputes() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_SETTLEMENT_DELAY => 1_000u64,
+        });
+        let escrow_admin = create_funded_account(&mut builder, 167);
+        let outsider = create_funded_account(&mut builder, 168);
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_ESCROW_ADMIN => escrow_admin,
+            },
+        );
+        let stored_admin: Option<AccountHash> = query_contract(
+            &mut builder,
+            "get_escrow_admin",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(stored_admin, Some(escrow_admin));
+
+        call_contract_as(
+            &mut builder,
+            escrow_admin,
+            "pause_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            outsider,
+            "unpause_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            117,
+        );
+        call_contract_as(
+            &mut builder,
+            escrow_admin,
+            "unpause_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        call_contract(
+            &mut builder,
+            "raise_dispute",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "resolve_dispute",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_CANCEL => false },
+            169,
+        );
+        call_contract_as(
+            &mut builder,
+            escrow_admin,
+            "resolve_dispute",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_CANCEL => false },
+        );
+        assert_eq!(escrows_by_status(&mut builder, 1, 0, 10), vec![1]);
+    }
+
+    #[test]
+    fn should_let_admin_refund_single_participant() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 169);
+        let stranger = create_funded_account(&mut builder, 170);
+        create_escrow(&mut builder, 1, 100, 300);
+        for participant in [*DEFAULT_ACCOUNT_ADDR, friend] {
+            call_with_purse_as(
+                &mut builder,
+                participant,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+            );
+        }
+
+        assert_user_error(
+            &mut builder,
+            friend,
+            "admin_refund_participant",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => friend },
+            107,
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "admin_refund_participant",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => stranger },
+            111,
+        );
+
+        let friend_purse = builder
+            .get_account(friend)
+            .expect("should have account")
+            .main_purse();
+        let balance_before = builder.get_purse_balance(friend_purse);
+        call_contract(
+            &mut builder,
+            "admin_refund_participant",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => friend },
+        );
+        assert_eq!(
+            builder.get_purse_balance(friend_purse),
+            balance_before + 100
+        );
+        let participants: Vec<AccountHash> = query_contract(
+            &mut builder,
+            "get_participants",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(participants, vec![*DEFAULT_ACCOUNT_ADDR]);
+        let joined_count: u64 = query_contract(
+            &mut builder,
+            "get_participant_count",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(joined_count, 1);
+        let pot: u64 = query_contract(
+            &mut builder,
+            "get_pot_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(pot, 100);
+    }
+
+    #[test]
+    fn should_store_verifier_and_check_it_on_join() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_NAME_PREFIX => "allowlist".to_string(),
+        });
+        // Any stored contract without `is_verified` stands in for a verifier here.
+        let verifier = builder
+            .get_named_keys_by_account_hash(*DEFAULT_ACCOUNT_ADDR)
+            .get(&format!("allowlist_{}", CONTRACT_KEY))
+            .and_then(|key| key.into_hash_addr())
+            .map(ContractHash::new)
+            .expect("should have allowlist contract hash");
+        let install_request = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            CONTRACT_WASM,
+            runtime_args! { ARG_VERIFIER_CONTRACT => verifier },
+        )
+        .build();
+        builder.exec(install_request).commit().expect_success();
+        let friend = create_funded_account(&mut builder, 171);
+        create_escrow(&mut builder, 1, 100, 300);
+
+        let stored: Option<ContractHash> =
+            query_contract(&mut builder, "get_verifier", runtime_args! {});
+        assert_eq!(stored, Some(verifier));
+
+        let request = ExecuteRequestBuilder::contract_call_by_hash(
+            friend,
+            contract_hash(&builder),
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        )
+        .build();
+        builder.exec(request).commit().expect_failure();
+    }
+
+    #[test]
+    fn should_not_require_verification_without_verifier() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 172);
+        create_escrow(&mut builder, 1, 100, 300);
+
+        let stored: Option<ContractHash> =
+            query_contract(&mut builder, "get_verifier", runtime_args! {});
+        assert_eq!(stored, None);
+
+        call_contract_as(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+    }
+
+    #[test]
+    fn should_index_escrows_by_tag() {
+        let mut builder = install_contract();
+        let tagged = [
+            (1u64, vec!["dinner", "friends"]),
+            (2, vec!["trip"]),
+            (3, vec!["dinner"]),
+        ];
+        for (escrow_id, tags) in tagged {
+            let tags: Vec<String> = tags.into_iter().map(String::from).collect();
+            call_contract(
+                &mut builder,
+                "create_escrow",
+                runtime_args! {
+                    ARG_ESCROW_ID => escrow_id,
+                    ARG_AMOUNT => 100u64,
+                    ARG_TARGET_AMOUNT => 300u64,
+                    ARG_TAGS => tags,
+                },
+            );
+        }
+        create_escrow(&mut builder, 4, 100, 300);
+
+        assert_eq!(escrows_by_tag(&mut builder, "dinner", 0, 10), vec![1, 3]);
+        assert_eq!(escrows_by_tag(&mut builder, "dinner", 1, 10), vec![3]);
+        assert_eq!(escrows_by_tag(&mut builder, "dinner", 0, 1), vec![1]);
+        assert_eq!(escrows_by_tag(&mut builder, "friends", 0, 10), vec![1]);
+        assert_eq!(escrows_by_tag(&mut builder, "trip", 0, 10), vec![2]);
+        assert!(escrows_by_tag(&mut builder, "rent", 0, 10).is_empty());
+    }
+
+    #[test]
+    fn should_reject_invalid_tags() {
+        let mut builder = install_contract();
+        let invalid: [Vec<String>; 3] = [
+            (0..6).map(|i| format!("tag{}", i)).collect(),
+            vec!["x".repeat(33)],
+            vec!["dinner".to_string(), "dinner".to_string()],
+        ];
+        for tags in invalid {
+            assert_user_error(
+                &mut builder,
+                *DEFAULT_ACCOUNT_ADDR,
+                "create_escrow",
+                runtime_args! {
+                    ARG_ESCROW_ID => 1u64,
+                    ARG_AMOUNT => 100u64,
+                    ARG_TARGET_AMOUNT => 300u64,
+                    ARG_TAGS => tags,
+                },
+                171,
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_contributions_above_accumulated_cap() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 173);
+        let friend = create_funded_account(&mut builder, 174);
+        create_escrow(&mut builder, 2, 100, 300);
+        call_contract_as(
+            &mut builder,
+            creator,
+            "create_open_pot",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_MIN_CONTRIBUTION => 50u64,
+                ARG_MAX_ACCUMULATED => 150u64,
+            },
+        );
+
+        let capped: u64 = query_contract(
+            &mut builder,
+            "get_accumulated_cap",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(capped, 150);
+        let uncapped: u64 = query_contract(
+            &mut builder,
+            "get_accumulated_cap",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(uncapped, u64::MAX);
+
+        call_with_purse(
+            &mut builder,
+            "contribute",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        assert_session_user_error(
+            &mut builder,
+            friend,
+            "contribute",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+            172,
+        );
+    }
+
+    #[test]
+    fn should_price_unfilled_slots() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 250);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+
+        let middle: u64 = query_contract(
+            &mut builder,
+            "get_slot_amount",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_SLOT_INDEX => 1u64 },
+        );
+        assert_eq!(middle, 100);
+        let last: u64 = query_contract(
+            &mut builder,
+            "get_slot_amount",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_SLOT_INDEX => 2u64 },
+        );
+        assert_eq!(last, 50);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "get_slot_amount",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_SLOT_INDEX => 0u64 },
+            174,
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "get_slot_amount",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_SLOT_INDEX => 3u64 },
+            173,
+        );
+    }
+
+    #[test]
+    fn should_block_settlement_but_allow_refunds_while_frozen() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 175);
+        create_escrow(&mut builder, 1, 100, 200);
+        create_escrow(&mut builder, 2, 100, 100);
+        for escrow_id in [1u64, 2] {
+            call_with_purse(
+                &mut builder,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => escrow_id, ARG_AMOUNT => 100u64 },
+            );
+        }
+
+        assert_user_error(&mut builder, friend, "freeze", runtime_args! {}, 107);
+        call_contract(&mut builder, "freeze", runtime_args! {});
+        let frozen: bool = query_contract(&mut builder, "is_frozen", runtime_args! {});
+        assert!(frozen);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+            175,
+        );
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 3u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+            },
+            175,
+        );
+        assert_user_error(
+            &mut builder,
+            friend,
+            "join_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            175,
+        );
+        call_with_purse(
+            &mut builder,
+            "refund",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+
+        call_contract(&mut builder, "unfreeze", runtime_args! {});
+        let frozen: bool = query_contract(&mut builder, "is_frozen", runtime_args! {});
+        assert!(!frozen);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+    }
+
+    #[test]
+    fn should_delay_settlement_after_last_join() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_MIN_SETTLE_DELAY_AFTER_LAST_JOIN => 1_000u64,
+        });
+        let friend = create_funded_account(&mut builder, 176);
+        create_escrow(&mut builder, 1, 100, 200);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        set_block_time(block_time() + 500);
+        let last_join = block_time();
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+
+        let earliest: u64 = query_contract(
+            &mut builder,
+            "get_earliest_settle_time",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(earliest, last_join + 1_000);
+
+        set_block_time(last_join + 999);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            109,
+        );
+        set_block_time(last_join + 1_000);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+    }
+
+    #[test]
+    fn should_return_account_positions_across_roles() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 177);
+        call_contract_as(
+            &mut builder,
+            friend,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+            },
+        );
+        create_escrow(&mut builder, 2, 100, 300);
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 2u64, ARG_AMOUNT => 100u64 },
+        );
+
+        let positions: BTreeMap<u64, (u8, u8, u64)> = query_contract(
+            &mut builder,
+            "get_account_positions",
+            runtime_args! { ARG_ACCOUNT => friend },
+        );
+        let expected: BTreeMap<u64, (u8, u8, u64)> =
+            [(1, (0, 0, 0)), (2, (1, 0, 100))].into_iter().collect();
+        assert_eq!(positions, expected);
+
+        let stranger = create_funded_account(&mut builder, 178);
+        let positions: BTreeMap<u64, (u8, u8, u64)> = query_contract(
+            &mut builder,
+            "get_account_positions",
+            runtime_args! { ARG_ACCOUNT => stranger },
+        );
+        assert!(positions.is_empty());
+    }
+
+    #[test]
+    fn should_resolve_participant_display_names() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 179);
+        let quiet = create_funded_account(&mut builder, 180);
+        create_escrow(&mut builder, 1, 100, 300);
+        for participant in [*DEFAULT_ACCOUNT_ADDR, friend, quiet] {
+            call_contract_as(
+                &mut builder,
+                participant,
+                "join_escrow",
+                runtime_args! { ARG_ESCROW_ID => 1u64 },
+            );
+        }
+        call_contract(
+            &mut builder,
+            "set_display_name",
+            runtime_args! { ARG_DISPLAY_NAME => "Alice".to_string() },
+        );
+        call_contract_as(
+            &mut builder,
+            friend,
+            "set_display_name",
+            runtime_args! { ARG_DISPLAY_NAME => "Bob".to_string() },
+        );
+
+        let names: Vec<(AccountHash, String)> = query_contract(
+            &mut builder,
+            "get_participant_names",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(
+            names,
+            vec![
+                (*DEFAULT_ACCOUNT_ADDR, "Alice".to_string()),
+                (friend, "Bob".to_string()),
+            ]
+        );
+
+        for invalid in ["x".repeat(33), "Bob\n".to_string()] {
+            assert_user_error(
+                &mut builder,
+                friend,
+                "set_display_name",
+                runtime_args! { ARG_DISPLAY_NAME => invalid },
+                176,
+            );
+        }
+        call_contract_as(
+            &mut builder,
+            friend,
+            "set_display_name",
+            runtime_args! { ARG_DISPLAY_NAME => String::new() },
+        );
+        let names: Vec<(AccountHash, String)> = query_contract(
+            &mut builder,
+            "get_participant_names",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(names, vec![(*DEFAULT_ACCOUNT_ADDR, "Alice".to_string())]);
+    }
+
+    #[test]
+    fn should_settle_ready_escrows_in_batch_and_skip_the_rest() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 100);
+        create_escrow(&mut builder, 2, 100, 300);
+        create_escrow(&mut builder, 3, 100, 100);
+        for escrow_id in [1u64, 2, 3] {
+            call_with_purse(
+                &mut builder,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => escrow_id, ARG_AMOUNT => 100u64 },
+            );
+        }
+
+        let settled: Vec<bool> = query_contract(
+            &mut builder,
+            "settle_batch",
+            runtime_args! { ARG_ESCROW_IDS => vec![1u64, 2, 3, 99] },
+        );
+        assert_eq!(settled, vec![true, false, true, false]);
+        let mut completed = escrows_by_status(&mut builder, 2, 0, 10);
+        completed.sort();
+        assert_eq!(completed, vec![1, 3]);
+        assert_eq!(escrows_by_status(&mut builder, 0, 0, 10), vec![2]);
+
+        let settled: Vec<bool> = query_contract(
+            &mut builder,
+            "settle_batch",
+            runtime_args! { ARG_ESCROW_IDS => vec![1u64] },
+        );
+        assert_eq!(settled, vec![false]);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "settle_batch",
+            runtime_args! { ARG_ESCROW_IDS => (1u64..=11).collect::<Vec<u64>>() },
+            118,
+        );
+    }
+
+    #[test]
+    fn should_report_oracle_unavailable_without_price_oracle() {
+        let mut builder = install_contract();
+        create_escrow(&mut builder, 1, 100, 300);
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "get_value_in_quote",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            177,
+        );
+    }
+
+    #[test]
+    fn should_store_price_oracle_and_query_it_for_quotes() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_NAME_PREFIX => "prices".to_string(),
+        });
+        // Any stored contract without `get_cspr_price` stands in for an oracle here.
+        let price_oracle = builder
+            .get_named_keys_by_account_hash(*DEFAULT_ACCOUNT_ADDR)
+            .get(&format!("prices_{}", CONTRACT_KEY))
+            .and_then(|key| key.into_hash_addr())
+            .map(ContractHash::new)
+            .expect("should have price oracle contract hash");
+        let install_request = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            CONTRACT_WASM,
+            runtime_args! { ARG_PRICE_ORACLE => price_oracle },
+        )
+        .build();
+        builder.exec(install_request).commit().expect_success();
+        create_escrow(&mut builder, 1, 100, 300);
+
+        let config: BTreeMap<String, String> =
+            query_contract(&mut builder, "get_config", RuntimeArgs::new());
+        assert_eq!(config["price_oracle"], price_oracle.to_string());
+
+        let request = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            contract_hash(&builder),
+            "get_value_in_quote",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        )
+        .build();
+        builder.exec(request).commit().expect_failure();
+    }
+
+    #[test]
+    fn should_fund_several_slots_in_one_transfer() {
+        let mut builder = install_contract();
+        let friends: Vec<AccountHash> = (181u8..=183)
+            .map(|seed| create_funded_account(&mut builder, seed))
+            .collect();
+        create_escrow(&mut builder, 1, 100, 400);
+
+        assert_session_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "fund_slots",
+            250,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_BENEFICIARIES => friends.clone(),
+                ARG_AMOUNT => 250u64,
+            },
+            163,
+        );
+        call_with_purse(
+            &mut builder,
+            "fund_slots",
+            300,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_BENEFICIARIES => friends.clone(),
+                ARG_AMOUNT => 300u64,
+            },
+        );
+
+        let participants: Vec<AccountHash> = query_contract(
+            &mut builder,
+            "get_participants",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(participants, friends);
+        for friend in &friends {
+            let stake: u64 = query_contract(
+                &mut builder,
+                "get_stake",
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *friend },
+            );
+            assert_eq!(stake, 100);
+        }
+
+        assert_session_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "fund_slots",
+            100,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_BENEFICIARIES => vec![friends[0]],
+                ARG_AMOUNT => 100u64,
+            },
+            178,
+        );
+    }
+
+    #[test]
+    fn should_refuse_to_settle_when_ledger_exceeds_purse() {
+        let mut builder = install_contract();
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_SETTLE_TO_CREATOR => true,
+            },
+        );
+        create_escrow(&mut builder, 2, 100, 300);
+        for escrow_id in [1u64, 2] {
+            call_with_purse(
+                &mut builder,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => escrow_id, ARG_AMOUNT => 100u64 },
+            );
+        }
+
+        // Inflate escrow 1's ledger past what its purse holds.
+        let total_staked_key = *builder
+            .get_named_keys_for_contract(contract_hash(&builder))
+            .get("escrow_1_total_staked")
+            .expect("should have total staked key");
+        builder.write_data_and_commit(std::iter::once((
+            total_staked_key.normalize(),
+            StoredValue::CLValue(CLValue::from_t(150u64).expect("should encode")),
+        )));
+
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            179,
+        );
+        let (_, escrow_2_balance): (u64, u64) = query_contract(
+            &mut builder,
+            "reconcile",
+            runtime_args! { ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(escrow_2_balance, 100);
+    }
+
+    #[test]
+    fn should_donate_charity_share_on_settlement() {
+        let mut builder = install_contract();
+        let creator = create_funded_account(&mut builder, 184);
+        let friend = create_funded_account(&mut builder, 185);
+        let charity = create_funded_account(&mut builder, 186);
+        assert_user_error(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_SETTLE_TO_CREATOR => true,
+                ARG_CHARITY => charity,
+                ARG_CHARITY_BPS => 10_001u64,
+            },
+            180,
+        );
+        assert_user_error(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_CHARITY => charity,
+                ARG_CHARITY_BPS => 1_000u64,
+            },
+            180,
+        );
+        call_contract_as(
+            &mut builder,
+            creator,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 100u64,
+                ARG_SETTLE_TO_CREATOR => true,
+                ARG_CHARITY => charity,
+                ARG_CHARITY_BPS => 1_000u64,
+            },
+        );
+        let charity_config: Option<(AccountHash, u64)> = query_contract(
+            &mut builder,
+            "get_charity_config",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(charity_config, Some((charity, 1_000)));
+
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        let preview: (u64, u64, u64) = query_contract(
+            &mut builder,
+            "preview_settlement",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(preview, (100, 0, 90));
+
+        let creator_purse = builder
+            .get_account(creator)
+            .expect("should have account")
+            .main_purse();
+        let charity_purse = builder
+            .get_account(charity)
+            .expect("should have account")
+            .main_purse();
+        let creator_before = builder.get_purse_balance(creator_purse);
+        let charity_before = builder.get_purse_balance(charity_purse);
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        assert_eq!(
+            builder.get_purse_balance(creator_purse),
+            creator_before + 90
+        );
+        assert_eq!(
+            builder.get_purse_balance(charity_purse),
+            charity_before + 10
+        );
+    }
+
+    #[test]
+    fn should_report_pending_operations() {
+        let mut builder = install_contract();
+        let friends: Vec<AccountHash> = (187..189)
+            .map(|seed| create_funded_account(&mut builder, seed))
+            .collect();
+        create_escrow(&mut builder, 1, 100, 300);
+
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+        for friend in &friends {
+            call_with_purse_as(
+                &mut builder,
+                *friend,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+            );
+        }
+
+        let earliest_settle_time: u64 = query_contract(
+            &mut builder,
+            "get_earliest_settle_time",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        let pending: BTreeMap<String, u64> = query_contract(
+            &mut builder,
+            "get_pending_operations",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(pending["settle_pending"], 1);
+        assert_eq!(pending["settle_after"], earliest_settle_time);
+        assert_eq!(pending["refunds_pending"], 0);
+        assert_eq!(pending["payout_pending"], 0);
+
+        call_contract(
+            &mut builder,
+            "refund_batch",
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_MAX_COUNT => 1u8 },
+        );
+        let pending: BTreeMap<String, u64> = query_contract(
+            &mut builder,
+            "get_pending_operations",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(pending["settle_pending"], 0);
+        assert_eq!(pending["refunds_pending"], 2);
+        assert_eq!(pending["refunds_pending_total"], 200);
+    }
+
+    #[test]
+    fn should_count_cancel_votes_against_staked_participants() {
+        assert_install_user_error(
+            runtime_args! { ARG_CANCEL_VOTE_THRESHOLD_BPS => 10_001u64 },
+            182,
+        );
+
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_SETTLEMENT_DELAY => 1_000u64,
+        });
+        let friend = create_funded_account(&mut builder, 189);
+        create_escrow(&mut builder, 1, 100, 300);
+
+        set_block_time(10_000);
+        call_with_purse(
+            &mut builder,
+            "stake",
+            100,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR,
+            },
+        );
+        call_with_purse_as(
+            &mut builder,
+            friend,
+            "stake",
+            200,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 200u64,
+                ARG_PARTICIPANT => friend,
+            },
+        );
+        call_contract(
+            &mut builder,
+            "raise_dispute",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+
+        call_contract(
+            &mut builder,
+            "vote_cancel",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(escrows_by_status(&mut builder, 3, 0, 10), vec![1]);
+
+        call_contract_as(
+            &mut builder,
+            friend,
+            "vote_cancel",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(escrows_by_status(&mut builder, 4, 0, 10), vec![1]);
+    }
+
+    #[test]
+    fn should_count_stake_funded_participants() {
+        let mut builder = install_contract();
+        let friends: Vec<AccountHash> = (191..193)
+            .map(|seed| create_funded_account(&mut builder, seed))
+            .collect();
+        create_escrow(&mut builder, 1, 100, 300);
+
+        call_with_purse(
+            &mut builder,
+            "stake",
+            100,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR,
+            },
+        );
+
+        let count: u64 = query_contract(
+            &mut builder,
+            "get_participant_count",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(count, 1);
+        let schedule: Vec<(u64, bool)> = query_contract(
+            &mut builder,
+            "get_split_schedule",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(schedule, vec![(100, true), (100, false), (100, false)]);
+
+        let mut beneficiaries = friends.clone();
+        beneficiaries.push(AccountHash::new([193; 32]));
+        assert_session_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "fund_slots",
+            300,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_BENEFICIARIES => beneficiaries,
+                ARG_AMOUNT => 300u64,
+            },
+            164,
+        );
+        call_with_purse(
+            &mut builder,
+            "fund_slots",
+            200,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_BENEFICIARIES => friends,
+                ARG_AMOUNT => 200u64,
+            },
+        );
+        let count: u64 = query_contract(
+            &mut builder,
+            "get_participant_count",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn should_refund_without_leave_fee_after_deadline() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_LEAVE_FEE_BPS => 1_000u64,
+            ARG_LATE_LEAVE_FEE_BPS => 2_000u64,
+        });
+        let deadline = block_time() + 1_000;
+        call_contract(
+            &mut builder,
+            "create_escrow",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 100u64,
+                ARG_TARGET_AMOUNT => 300u64,
+                ARG_DEADLINE => deadline,
+            },
+        );
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+
+        set_block_time(deadline);
+        let preview: u64 = query_contract(
+            &mut builder,
+            "preview_leave_refund",
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR,
+            },
+        );
+        assert_eq!(preview, 100);
+
+        call_with_purse(
+            &mut builder,
+            "refund",
+            0,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_PARTICIPANT => *DEFAULT_ACCOUNT_ADDR },
+        );
+        let total_refunded: u64 = read_contract_named_value(&builder, "total_refunded");
+        assert_eq!(total_refunded, 100);
+    }
+
+    #[test]
+    fn should_skip_insolvent_and_frozen_escrows_in_batch() {
+        let mut builder = install_contract();
+        for escrow_id in [1u64, 2, 3] {
+            create_escrow(&mut builder, escrow_id, 100, 100);
+            call_with_purse(
+                &mut builder,
+                "pay_installment",
+                100,
+                runtime_args! { ARG_ESCROW_ID => escrow_id, ARG_AMOUNT => 100u64 },
+            );
+        }
+
+        // Inflate escrow 2's ledger past what its purse holds.
+        let total_staked_key = *builder
+            .get_named_keys_for_contract(contract_hash(&builder))
+            .get("escrow_2_total_staked")
+            .expect("should have total staked key");
+        builder.write_data_and_commit(std::iter::once((
+            total_staked_key.normalize(),
+            StoredValue::CLValue(CLValue::from_t(150u64).expect("should encode")),
+        )));
+
+        let settled: Vec<bool> = query_contract(
+            &mut builder,
+            "settle_batch",
+            runtime_args! { ARG_ESCROW_IDS => vec![1u64, 2] },
+        );
+        assert_eq!(settled, vec![true, false]);
+
+        call_contract(&mut builder, "freeze", runtime_args! {});
+        let settled: Vec<bool> = query_contract(
+            &mut builder,
+            "settle_batch",
+            runtime_args! { ARG_ESCROW_IDS => vec![3u64] },
+        );
+        assert_eq!(settled, vec![false]);
+        let mut ready = escrows_by_status(&mut builder, 1, 0, 10);
+        ready.sort();
+        assert_eq!(ready, vec![2, 3]);
+    }
+
+    #[test]
+    fn should_keep_deposit_of_funded_escrow_past_deadline() {
+        let mut builder = install_contract_with_args(runtime_args! {
+            ARG_CREATION_DEPOSIT => 50u64,
+            ARG_DEFAULT_DEADLINE_OFFSET => 1_000u64,
+        });
+        create_escrow_with_deposit(&mut builder, 50);
+        call_with_purse(
+            &mut builder,
+            "pay_installment",
+            100,
+            runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 100u64 },
+        );
+
+        set_block_time(block_time() + 1_000);
+        assert_user_error(
+            &mut builder,
+            *DEFAULT_ACCOUNT_ADDR,
+            "claim_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+            135,
+        );
+
+        call_contract(
+            &mut builder,
+            "complete_escrow",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        call_contract(
+            &mut builder,
+            "claim_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        let deposit: u64 = query_contract(
+            &mut builder,
+            "get_creation_deposit",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(deposit, 0);
+    }
+
+    #[test]
+    fn should_keep_overfunded_source_ready_after_transfer() {
+        let mut builder = install_contract();
+        let friend = create_funded_account(&mut builder, 194);
+        create_escrow(&mut builder, 1, 50, 100);
+        create_escrow(&mut builder, 2, 50, 100);
+        for account in [*DEFAULT_ACCOUNT_ADDR, friend] {
+            call_with_purse_as(
+                &mut builder,
+                account,
+                "pay_installment",
+                50,
+                runtime_args! { ARG_ESCROW_ID => 1u64, ARG_AMOUNT => 50u64 },
+            );
+        }
+        let staker = create_funded_account(&mut builder, 195);
+        call_with_purse_as(
+            &mut builder,
+            staker,
+            "stake",
+            50,
+            runtime_args! {
+                ARG_ESCROW_ID => 1u64,
+                ARG_AMOUNT => 50u64,
+                ARG_PARTICIPANT => staker,
+            },
+        );
+
+        call_contract_as(
+            &mut builder,
+            friend,
+            "transfer_stake",
+            runtime_args! { ARG_SOURCE_ESCROW_ID => 1u64, ARG_ESCROW_ID => 2u64 },
+        );
+        assert_eq!(escrows_by_status(&mut builder, 1, 0, 10), vec![1]);
+        let pot: u64 = query_contract(
+            &mut builder,
+            "get_pot_balance",
+            runtime_args! { ARG_ESCROW_ID => 1u64 },
+        );
+        assert_eq!(pot, 100);
+    }
 }